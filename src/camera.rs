@@ -2,7 +2,7 @@ use bevy::{asset::RenderAssetUsages, math::{vec2, vec3}, prelude::*, render::{ca
 
 use crate::{
     player::Player, projection::{
-        zone_transform_center, MAP_SIZE_F32, TEXEL_SIZE, TEXEL_SIZE_F32, TILE_SIZE, TILE_SIZE_F32, ZONE_SIZE_F32
+        map_size_f32, zone_size_f32, zone_transform_center, TEXEL_SIZE, TEXEL_SIZE_F32, TILE_SIZE, TILE_SIZE_F32
     }, rendering::{BevyColorable, Palette, Position}, ui::{PanelGame, PanelLeft, ViewportDim}, GameState
 };
 
@@ -259,6 +259,6 @@ pub fn on_mouse_move(
         return;
     };
 
-    cursor.x = ((world_2d.x / TILE_SIZE_F32.0) + 0.5).floor().clamp(0., ZONE_SIZE_F32.0 * MAP_SIZE_F32.0) as usize;
-    cursor.y = ((world_2d.y / TILE_SIZE_F32.1) + 0.5).floor().clamp(0., ZONE_SIZE_F32.1 * MAP_SIZE_F32.1) as usize;
+    cursor.x = ((world_2d.x / TILE_SIZE_F32.0) + 0.5).floor().clamp(0., zone_size_f32().0 * map_size_f32().0) as usize;
+    cursor.y = ((world_2d.y / TILE_SIZE_F32.1) + 0.5).floor().clamp(0., zone_size_f32().1 * map_size_f32().1) as usize;
 }
@@ -66,6 +66,7 @@ where
 }
 
 // remap a number v that is between 0-1 to be between min and max
+#[allow(dead_code)]
 pub fn remap(v: f32, min: f32, max: f32) -> f32 {
     (v * (max - min)) + min
 }
@@ -44,4 +44,8 @@ impl Rand {
     pub fn bool(&mut self, chance: f32) -> bool {
         self.random() < chance
     }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.r.r#gen()
+    }
 }
@@ -1,6 +1,8 @@
+use serde::{Deserialize, Serialize};
+
 // A Column-major 3D grid
 #[allow(dead_code)]
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Grid3d<T> {
     data: Vec<T>,
     width: usize,
@@ -24,7 +26,7 @@ impl<T> Grid3d<T> {
         T: Clone,
     {
         Self {
-            data: vec![value; width * height],
+            data: vec![value; width * height * depth],
             width,
             height,
             depth,
@@ -3,7 +3,11 @@ use camera::CameraPlugin;
 use player::PlayerPlugin;
 use rendering::{setup_tileset, BevyColorable, GlyphPlugin, GlyphTextPlugin, Palette, TilesetTextures};
 use ui::{UiPlugin, ViewportPlugin};
-use world::{MapPlugin, ZoneSnapshotPlugin};
+use save::try_load_world;
+use world::{
+    prefabs_loaded, samples_loaded, MapPlugin, PrefabPlugin, SamplePlugin, WorldConfig, WorldMapPlugin,
+    ZoneSnapshotPlugin,
+};
 
 mod camera;
 mod common;
@@ -20,6 +24,7 @@ pub enum GameState {
     Loading,
     Playing,
     Snapshot,
+    WorldMap,
 }
 
 pub fn go_to_state(state: GameState) -> impl Fn(ResMut<NextState<GameState>>) {
@@ -29,11 +34,23 @@ pub fn go_to_state(state: GameState) -> impl Fn(ResMut<NextState<GameState>>) {
 }
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()))
+    let mut app = App::new();
+
+    app.add_plugins(DefaultPlugins.set(ImagePlugin::default_nearest()));
+
+    // the world size has to be known before anything is generated, and is
+    // read once logging is up so a clamped or invalid override is reported.
+    // a cli/env override wins over a saved world, which wins over the default.
+    let saved = try_load_world().map(|w| w.config).unwrap_or_default();
+    let config = WorldConfig::from_override(saved).unwrap_or(saved).install();
+
+    app.insert_resource(config)
         .add_plugins(CameraPlugin)
         .add_plugins(ZoneSnapshotPlugin)
         .add_plugins(MapPlugin)
+        .add_plugins(PrefabPlugin)
+        .add_plugins(SamplePlugin)
+        .add_plugins(WorldMapPlugin)
         .add_plugins(GlyphPlugin)
         .add_plugins(GlyphTextPlugin)
         .add_plugins(PlayerPlugin)
@@ -42,9 +59,14 @@ fn main() {
         .init_state::<GameState>()
         .insert_resource(ClearColor(0x000000_u32.to_bevy_color()))
         .init_resource::<TilesetTextures>()
+        .add_systems(OnEnter(GameState::Loading), setup_tileset)
+        // no zone is built until everything zones are built from has loaded
         .add_systems(
-            OnEnter(GameState::Loading),
-            (setup_tileset, go_to_state(GameState::Playing)).chain(),
+            Update,
+            go_to_state(GameState::Playing)
+                .run_if(in_state(GameState::Loading))
+                .run_if(prefabs_loaded)
+                .run_if(samples_loaded),
         )
         .run();
 }
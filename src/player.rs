@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use bevy::prelude::*;
 
 use crate::{
-    camera::Layer, projection::{MAP_SIZE, ZONE_SIZE, Z_LAYER_ACTORS, Z_LAYER_TEXT}, rendering::{Glyph, Palette, Position, Text, Tile}, ui::UiBox, GameState
+    camera::Layer, projection::{map_size, world_to_zone_idx, world_to_zone_local, zone_size, Z_LAYER_ACTORS, Z_LAYER_TEXT}, rendering::{Glyph, Palette, Position, Text, Tile}, ui::UiBox, world::{Terrain, Zone}, GameState
 };
 
 pub struct PlayerPlugin;
@@ -12,13 +12,21 @@ impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut bevy::app::App) {
         app.add_event::<PlayerMovedEvent>()
             .add_systems(Startup, (setup_player).chain())
-            .add_systems(Update, (player_input).run_if(in_state(GameState::Playing)));
+            .add_systems(Update, (player_input, update_swimming).chain().run_if(in_state(GameState::Playing)));
     }
 }
 
 #[derive(Component)]
 pub struct Player;
 
+// swimming is slower than wading through anything
+const SWIM_COST: f64 = 4.;
+
+// the player is in water too deep to wade. They can't climb stairs or
+// ladders until they are out of it.
+#[derive(Component)]
+pub struct Swimming;
+
 #[derive(Event)]
 pub struct PlayerMovedEvent {
     pub x: usize,
@@ -35,15 +43,6 @@ pub fn setup_player(mut cmds: Commands, mut e_player_moved: EventWriter<PlayerMo
 
     e_player_moved.send(PlayerMovedEvent { x: 8, y: 8, z: 0 });
 
-    cmds.spawn((
-        Text::new(" You discovered... ").bg(Palette::Black).fg1(Palette::White),
-        Position::f32(17.0, 15., 0.0, Layer::Ui),
-    ));
-
-    cmds.spawn((
-        Text::title("♦ {C|ESPERLOOSA OUTPOST} ♦").bg(Palette::Black).fg1(Palette::Yellow).fg2(Palette::Cyan),
-        Position::f32(16.0, 14., 0.0, Layer::Ui),
-    ));
     cmds.spawn((
         Text::new("Under the {C-b border|vast, starry sky}, the {R-O-Y-G-B-P stretch|cowboy's} {R|heart} ached").bg(Palette::Black),
         Position::f32(4.0, 6.0, 0.0, Layer::Fx),
@@ -134,7 +133,8 @@ impl InputRate {
 }
 
 pub fn player_input(
-    mut q_player: Query<&mut Position, With<Player>>,
+    mut q_player: Query<(&mut Position, Has<Swimming>), With<Player>>,
+    q_zones: Query<&Zone>,
     keys: Res<ButtonInput<KeyCode>>,
     time: Res<Time>,
     mut input_rate: Local<InputRate>,
@@ -145,28 +145,35 @@ pub fn player_input(
     let delay = 0.25;
     let mut moved = false;
 
-    let mut position = q_player.single_mut();
+    let (mut position, is_swimming) = q_player.single_mut();
     let (x, y, z) = position.world();
+    let standing_on = terrain_at(&q_zones, x, y, z);
+
+    // wading and swimming is slower than walking, by the tile moved onto
+    let rate_onto = |x: usize, y: usize| match terrain_at(&q_zones, x, y, z) {
+        Some(t) => rate * t.move_cost().map_or(SWIM_COST, |c| c as f64),
+        None => rate,
+    };
 
     if x > 0
         && keys.pressed(KeyCode::KeyA)
-        && input_rate.try_key(KeyCode::KeyA, now, rate, delay)
+        && input_rate.try_key(KeyCode::KeyA, now, rate_onto(x - 1, y), delay)
     {
         position.x(x - 1);
         moved = true;
     }
 
-    if x < (MAP_SIZE.0 * ZONE_SIZE.0) - 1
+    if x < (map_size().0 * zone_size().0) - 1
         && keys.pressed(KeyCode::KeyD)
-        && input_rate.try_key(KeyCode::KeyD, now, rate, delay)
+        && input_rate.try_key(KeyCode::KeyD, now, rate_onto(x + 1, y), delay)
     {
         position.x(x + 1);
         moved = true;
     }
 
-    if y < (MAP_SIZE.1 * ZONE_SIZE.1) - 1
+    if y < (map_size().1 * zone_size().1) - 1
         && keys.pressed(KeyCode::KeyW)
-        && input_rate.try_key(KeyCode::KeyW, now, rate, delay)
+        && input_rate.try_key(KeyCode::KeyW, now, rate_onto(x, y + 1), delay)
     {
         position.y(y + 1);
         moved = true;
@@ -174,13 +181,15 @@ pub fn player_input(
 
     if y > 0
         && keys.pressed(KeyCode::KeyS)
-        && input_rate.try_key(KeyCode::KeyS, now, rate, delay)
+        && input_rate.try_key(KeyCode::KeyS, now, rate_onto(x, y - 1), delay)
     {
         position.y(y - 1);
         moved = true;
     }
 
     if z > 0
+        && !is_swimming
+        && standing_on.is_some_and(|t: Terrain| t.is_way_up())
         && keys.pressed(KeyCode::KeyE)
         && input_rate.try_key(KeyCode::KeyE, now, rate, delay)
     {
@@ -188,7 +197,9 @@ pub fn player_input(
         moved = true;
     }
 
-    if z < MAP_SIZE.2 - 1
+    if z < map_size().2 - 1
+        && !is_swimming
+        && standing_on.is_some_and(|t: Terrain| t.is_way_down())
         && keys.pressed(KeyCode::KeyQ)
         && input_rate.try_key(KeyCode::KeyQ, now, rate, delay)
     {
@@ -208,3 +219,43 @@ pub fn player_input(
         });
     }
 }
+
+// the player starts swimming when they move into deep water, and the water
+// shows around them
+pub fn update_swimming(
+    mut cmds: Commands,
+    mut q_player: Query<(Entity, &Position, &mut Glyph, Has<Swimming>), (With<Player>, Changed<Position>)>,
+    q_zones: Query<&Zone>,
+) {
+    for (player_e, position, mut glyph, was_swimming) in q_player.iter_mut() {
+        let (x, y, z) = position.world();
+        let is_swimming = terrain_at(&q_zones, x, y, z).is_some_and(|t| t.is_deep());
+
+        if is_swimming == was_swimming {
+            continue;
+        }
+
+        match is_swimming {
+            true => {
+                cmds.entity(player_e).insert(Swimming);
+                glyph.bg = Some(Palette::Blue.into());
+            }
+            false => {
+                cmds.entity(player_e).remove::<Swimming>();
+                glyph.bg = None;
+            }
+        }
+    }
+}
+
+// the terrain at a world position, if its zone is loaded
+fn terrain_at(q_zones: &Query<&Zone>, x: usize, y: usize, z: usize) -> Option<Terrain> {
+    let zone_idx = world_to_zone_idx(x, y, z);
+    let (local_x, local_y) = world_to_zone_local(x, y);
+
+    q_zones
+        .iter()
+        .find(|zone| zone.idx() == zone_idx)
+        .and_then(|zone| zone.get_terrain(local_x, local_y))
+        .copied()
+}
@@ -15,6 +15,7 @@ pub enum Palette {
     Green = 0x2E862E,
     LightGreen = 0x1AAF1A,
     Brown = 0x664D3C,
+    Gray = 0x7C8083,
     Blue = 0x294E94,
     LightBlue = 0x608ED3,
     Red = 0xA83A3A,
@@ -16,6 +16,22 @@ pub enum Tile {
     Water = 34,
     Cowboy = 146,
     Dirt = 19,
+    Sand = 128,
+    Gravel = 129,
+    Tree = 65,
+    DeadTree = 66,
+    Cactus = 67,
+    Shrub = 78,
+    Boulder = 51,
+    Reeds = 94,
+    Planks = 105,
+    Sign = 15,
+    Campfire = 8,
+    Fence = 103,
+    Rail = 110,
+    Stairs = 108,
+    LadderHole = 109,
+    Ladder = 111,
     Blank = 238,
     BoxTopRight = 223,
     BoxTop = 222,
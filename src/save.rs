@@ -7,22 +7,56 @@ use bevy::{
     log::{debug, error, warn},
     tasks::IoTaskPool,
 };
+use serde::{Deserialize, Serialize};
 
-use crate::world::ZoneData;
+use crate::world::{WorldConfig, WorldData, WorldSeed, ZoneData, OVERWORLD_VERSION};
 
 pub const ENABLE_SAVES: bool = false;
 
-pub fn save_zone(zone: &ZoneData) {
+// A zone on disk, along with the world it was built for. A zone from another
+// seed, world size or overworld version would not line up with the roads and
+// rivers around it.
+#[derive(Deserialize, Serialize)]
+struct ZoneSave {
+    seed: WorldSeed,
+    version: u32,
+    config: WorldConfig,
+    zone: ZoneData,
+}
+
+pub fn save_zone(seed: WorldSeed, config: WorldConfig, zone: ZoneData) {
     if !ENABLE_SAVES {
         return;
     }
 
-    let Ok(save_data) = ron::to_string(zone) else {
+    let file_path = format!("saves/zone-{}.ron", zone.idx);
+    let save = ZoneSave {
+        seed,
+        version: OVERWORLD_VERSION,
+        config,
+        zone,
+    };
+
+    let Ok(save_data) = ron::to_string(&save) else {
         error!("could not save zone!");
         return;
     };
 
-    let file_path = format!("saves/zone-{}.ron", zone.idx);
+    debug!("saving {}", file_path);
+    store(file_path, save_data);
+}
+
+pub fn save_world(world: &WorldData) {
+    if !ENABLE_SAVES {
+        return;
+    }
+
+    let Ok(save_data) = ron::to_string(world) else {
+        error!("could not save world!");
+        return;
+    };
+
+    let file_path = "saves/world.ron".to_string();
     debug!("saving {}", file_path);
     store(file_path, save_data);
 }
@@ -46,7 +80,8 @@ fn store(file_path: String, data: String) -> Option<()> {
     storage.set_item(&file_path, &data).ok()
 }
 
-pub fn try_load_zone(zone_idx: usize) -> Option<ZoneData> {
+// the saved zone, if it was built for this seed, world size and overworld
+pub fn try_load_zone(zone_idx: usize, seed: WorldSeed, config: WorldConfig) -> Option<ZoneData> {
     if !ENABLE_SAVES {
         return None;
     }
@@ -57,12 +92,36 @@ pub fn try_load_zone(zone_idx: usize) -> Option<ZoneData> {
 
     let contents = read(&file_path)?;
 
-    let Ok(zone) = ron::from_str::<ZoneData>(&contents) else {
+    let Ok(save) = ron::from_str::<ZoneSave>(&contents) else {
         warn!("Could not deserialize zone save! corrupt? {}", file_path);
         return None;
     };
 
-    Some(zone)
+    if save.seed != seed || save.version != OVERWORLD_VERSION || save.config != config {
+        warn!("Zone save belongs to another world, ignoring {}", file_path);
+        return None;
+    }
+
+    Some(save.zone)
+}
+
+pub fn try_load_world() -> Option<WorldData> {
+    if !ENABLE_SAVES {
+        return None;
+    }
+
+    let file_path = "saves/world.ron".to_string();
+
+    debug!("loading {}", file_path);
+
+    let contents = read(&file_path)?;
+
+    let Ok(world) = ron::from_str::<WorldData>(&contents) else {
+        warn!("Could not deserialize world save! corrupt? {}", file_path);
+        return None;
+    };
+
+    Some(world)
 }
 
 #[cfg(not(target_arch = "wasm32"))]
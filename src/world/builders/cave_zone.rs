@@ -0,0 +1,356 @@
+use crate::{
+    common::{astar, AStarSettings, Distance, Grid, Rand},
+    projection::zone_size,
+    world::{Structure, Terrain},
+};
+
+use super::{
+    bool_snapshot, BuildContext, BuildStep, BuildStructure, NoiseLayer, PlaceStairs, Validate,
+    ZonePipeline, ZoneSnapshot,
+};
+
+// chance a tile starts out as rock before smoothing
+const CAVE_FILL: f32 = 0.45;
+const CAVE_SMOOTHING: usize = 5;
+// chance an underground river runs through the zone
+const CAVE_RIVER_CHANCE: f32 = 0.3;
+// noise above this floods the cave floor into pools
+const CAVE_POOL_THRESHOLD: f32 = 0.72;
+
+// Builds the zones beneath the surface. Caverns are grown with cellular
+// automata, then every cavern is tunneled into the others so the stairs
+// from the levels above and below can always be reached. The zone edge is
+// always rock, so caves never open into the zones next door and there are
+// no edge masks to match.
+pub fn cave_pipeline() -> ZonePipeline {
+    ZonePipeline::new()
+        .then(GrowCaverns)
+        .then(OpenChambers)
+        .then(ConnectCaverns)
+        .then(CaveTerrain)
+        .then(NoiseLayer {
+            name: "pools",
+            salt: 14,
+            frequency: 0.12,
+            octaves: 2,
+            lacunarity: 2.,
+        })
+        .then(FloodPools)
+        .then(BuildStructure)
+        .then(CaveRiver)
+        .then(PlaceStairs)
+        .then(Validate)
+}
+
+// the caves are worked out on a mask of solid rock before they are turned
+// into terrain
+fn rock_snapshot(ctx: &BuildContext) -> ZoneSnapshot {
+    bool_snapshot(&ctx.masks["rock"])
+}
+
+pub struct GrowCaverns;
+
+impl BuildStep for GrowCaverns {
+    fn name(&self) -> &'static str {
+        "caverns"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let r = &mut ctx.rand;
+
+        let mut rock = Grid::init_fill(zone_size().0, zone_size().1, |x, y| {
+            is_zone_edge(x, y) || r.bool(CAVE_FILL)
+        });
+
+        for _ in 0..CAVE_SMOOTHING {
+            rock = smooth(&rock);
+        }
+
+        ctx.masks.insert("rock", rock);
+    }
+
+    fn snapshot(&self, ctx: &BuildContext) -> ZoneSnapshot {
+        rock_snapshot(ctx)
+    }
+}
+
+// open a small chamber around each stair, and the rooms of any structure
+pub struct OpenChambers;
+
+impl BuildStep for OpenChambers {
+    fn name(&self) -> &'static str {
+        "chambers"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let c = &ctx.constraints;
+        let rock = ctx.masks.get_mut("rock").unwrap();
+
+        for stair in [c.stair_up, c.stair_down].iter().flatten() {
+            for x in stair.x - 1..=stair.x + 1 {
+                for y in stair.y - 1..=stair.y + 1 {
+                    rock.set(x, y, false);
+                }
+            }
+        }
+
+        // the rooms of a structure are open, and reached through the door
+        if let Some(structure) = &c.structure {
+            for x in structure.x..structure.x + structure.width {
+                for y in structure.y..structure.y + structure.height {
+                    rock.set(x, y, structure.is_wall(x, y));
+                }
+            }
+
+            let (dx, dy) = structure.door();
+            rock.set(dx, dy - 1, false);
+        }
+    }
+
+    fn snapshot(&self, ctx: &BuildContext) -> ZoneSnapshot {
+        rock_snapshot(ctx)
+    }
+}
+
+pub struct ConnectCaverns;
+
+impl BuildStep for ConnectCaverns {
+    fn name(&self) -> &'static str {
+        "tunnels"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let rock = ctx.masks.get_mut("rock").unwrap();
+
+        connect_caverns(rock, ctx.constraints.structure.as_ref());
+    }
+
+    fn snapshot(&self, ctx: &BuildContext) -> ZoneSnapshot {
+        rock_snapshot(ctx)
+    }
+}
+
+pub struct CaveTerrain;
+
+impl BuildStep for CaveTerrain {
+    fn name(&self) -> &'static str {
+        "cave"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        ctx.terrain = ctx.masks["rock"].map(|_, _, is_rock| match is_rock {
+            true => Terrain::CaveWall,
+            false => Terrain::CaveFloor,
+        });
+    }
+}
+
+// pools collect in the open parts of the cave
+pub struct FloodPools;
+
+impl BuildStep for FloodPools {
+    fn name(&self) -> &'static str {
+        "pools"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let rock = &ctx.masks["rock"];
+        let pools = &ctx.layers["pools"];
+
+        for x in 0..zone_size().0 {
+            for y in 0..zone_size().1 {
+                if !rock.get(x, y).unwrap() && *pools.get(x, y).unwrap() > CAVE_POOL_THRESHOLD {
+                    ctx.terrain.set(x, y, Terrain::River);
+                }
+            }
+        }
+    }
+}
+
+pub struct CaveRiver;
+
+impl BuildStep for CaveRiver {
+    fn name(&self) -> &'static str {
+        "river"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        if ctx.rand.bool(CAVE_RIVER_CHANCE) {
+            let structure = ctx.constraints.structure.as_ref();
+
+            carve_river(&mut ctx.terrain, &ctx.masks["rock"], structure, &mut ctx.rand);
+        }
+    }
+}
+
+fn is_zone_edge(x: usize, y: usize) -> bool {
+    x == 0 || y == 0 || x == zone_size().0 - 1 || y == zone_size().1 - 1
+}
+
+fn neighbors(x: usize, y: usize) -> Vec<(usize, usize)> {
+    let mut n = vec![];
+
+    for nx in x.saturating_sub(1)..=(x + 1).min(zone_size().0 - 1) {
+        for ny in y.saturating_sub(1)..=(y + 1).min(zone_size().1 - 1) {
+            if nx != x || ny != y {
+                n.push((nx, ny));
+            }
+        }
+    }
+
+    n
+}
+
+// one step of the cave automata, a tile becomes rock when most of its
+// neighbours are rock. The zone edge is always rock.
+fn smooth(rock: &Grid<bool>) -> Grid<bool> {
+    Grid::init_fill(zone_size().0, zone_size().1, |x, y| {
+        if is_zone_edge(x, y) {
+            return true;
+        }
+
+        let walls = neighbors(x, y)
+            .iter()
+            .filter(|(nx, ny)| *rock.get(*nx, *ny).unwrap())
+            .count();
+
+        walls >= 5 || (walls == 4 && *rock.get(x, y).unwrap())
+    })
+}
+
+// label each separate open area of the cave
+fn find_caverns(rock: &Grid<bool>) -> Vec<Vec<(usize, usize)>> {
+    let mut seen = Grid::init(zone_size().0, zone_size().1, false);
+    let mut caverns = vec![];
+
+    for x in 0..zone_size().0 {
+        for y in 0..zone_size().1 {
+            if *rock.get(x, y).unwrap() || *seen.get(x, y).unwrap() {
+                continue;
+            }
+
+            let mut cavern = vec![];
+            let mut open = vec![(x, y)];
+            seen.set(x, y, true);
+
+            while let Some((cx, cy)) = open.pop() {
+                cavern.push((cx, cy));
+
+                for (nx, ny) in neighbors(cx, cy) {
+                    if !rock.get(nx, ny).unwrap() && !seen.get(nx, ny).unwrap() {
+                        seen.set(nx, ny, true);
+                        open.push((nx, ny));
+                    }
+                }
+            }
+
+            caverns.push(cavern);
+        }
+    }
+
+    caverns
+}
+
+// tunnel from every cavern into the largest one, never through the walls
+// of a structure
+pub fn connect_caverns(rock: &mut Grid<bool>, structure: Option<&Structure>) {
+    let mut caverns = find_caverns(rock);
+    caverns.sort_by_key(|c| std::cmp::Reverse(c.len()));
+
+    let Some(main) = caverns.first().map(|c| c[c.len() / 2]) else {
+        return;
+    };
+
+    for cavern in caverns.iter().skip(1) {
+        let start = cavern[cavern.len() / 2];
+
+        let result = astar(AStarSettings {
+            start,
+            is_goal: |p| p == main,
+            cost: |_, (x, y)| match rock.get(x, y).unwrap() {
+                true => 3.,
+                false => 1.,
+            },
+            heuristic: |(x, y)| {
+                Distance::manhattan([x as i32, y as i32, 0], [main.0 as i32, main.1 as i32, 0])
+            },
+            neighbors: |(x, y)| {
+                // tunnels only move orthogonally, so they are never
+                // squeezed through a diagonal gap in the rock
+                let mut n = vec![];
+
+                if x > 1 {
+                    n.push((x - 1, y));
+                }
+                if x < zone_size().0 - 2 {
+                    n.push((x + 1, y));
+                }
+                if y > 1 {
+                    n.push((x, y - 1));
+                }
+                if y < zone_size().1 - 2 {
+                    n.push((x, y + 1));
+                }
+
+                n.retain(|(nx, ny)| !structure.is_some_and(|s| s.is_wall(*nx, *ny)));
+                n
+            },
+            max_depth: 10000,
+        });
+
+        for (x, y) in result.path {
+            rock.set(x, y, false);
+        }
+    }
+}
+
+// an underground river wells up out of the rock on one side of the zone,
+// and sinks back into it on the other, running around any structure
+fn carve_river(terrain: &mut Grid<Terrain>, rock: &Grid<bool>, structure: Option<&Structure>, r: &mut Rand) {
+    let is_built = |(x, y): (usize, usize)| structure.is_some_and(|s| s.contains(x, y));
+
+    let (start, goal) = match r.bool(0.5) {
+        true => (
+            (1, r.range_n(1, zone_size().1 as i32 - 1) as usize),
+            (zone_size().0 - 2, r.range_n(1, zone_size().1 as i32 - 1) as usize),
+        ),
+        false => (
+            (r.range_n(1, zone_size().0 as i32 - 1) as usize, 1),
+            (r.range_n(1, zone_size().0 as i32 - 1) as usize, zone_size().1 - 2),
+        ),
+    };
+
+    if is_built(start) || is_built(goal) {
+        return;
+    }
+
+    let result = astar(AStarSettings {
+        start,
+        is_goal: |p| p == goal,
+        // rivers favour the open cave, but will cut through rock
+        cost: |_, (x, y)| match rock.get(x, y).unwrap() {
+            true => 4.,
+            false => 1.,
+        },
+        heuristic: |(x, y)| {
+            Distance::chebyshev([x as i32, y as i32, 0], [goal.0 as i32, goal.1 as i32, 0])
+        },
+        neighbors: |(x, y)| {
+            neighbors(x, y)
+                .into_iter()
+                .filter(|(nx, ny)| !is_zone_edge(*nx, *ny) && !is_built((*nx, *ny)))
+                .collect()
+        },
+        max_depth: 10000,
+    });
+
+    if !result.is_success {
+        return;
+    }
+
+    terrain.set(start.0, start.1, Terrain::River);
+
+    for (x, y) in result.path {
+        terrain.set(x, y, Terrain::River);
+    }
+}
@@ -1,37 +1,39 @@
+use bevy::log::warn;
+
 use crate::{
-    common::{Grid, Perlin, Rand},
-    projection::ZONE_SIZE,
-    world::Terrain,
+    common::{astar, AStarSettings, Distance, Grid, Perlin, Rand},
+    projection::{zone_local_to_world, zone_size},
+    world::{Stair, Terrain},
 };
 
 use super::{TileSnapColor, ZoneConstraints, ZoneSnapshot};
 
 pub fn edge_snapshot(constraints: &ZoneConstraints) -> ZoneSnapshot {
-    let mut data = Grid::init(ZONE_SIZE.0, ZONE_SIZE.1, TileSnapColor::White);
+    let mut data = Grid::init(zone_size().0, zone_size().1, TileSnapColor::White);
 
     for (x, v) in constraints.north.iter().enumerate() {
-        data.set(x, ZONE_SIZE.1 - 1, TileSnapColor::for_edge(*v));
+        data.set(x, zone_size().1 - 1, TileSnapColor::for_edge(v));
     }
 
     for (x, v) in constraints.south.iter().enumerate() {
-        data.set(x, 0, TileSnapColor::for_edge(*v));
+        data.set(x, 0, TileSnapColor::for_edge(v));
     }
 
     for (y, v) in constraints.east.iter().enumerate() {
-        data.set(ZONE_SIZE.0 - 1, y, TileSnapColor::for_edge(*v));
+        data.set(zone_size().0 - 1, y, TileSnapColor::for_edge(v));
     }
 
     for (y, v) in constraints.west.iter().enumerate() {
-        data.set(0, y, TileSnapColor::for_edge(*v));
+        data.set(0, y, TileSnapColor::for_edge(v));
     }
 
-    ZoneSnapshot { data }
+    ZoneSnapshot { name: "edges", data }
 }
 
 pub fn grayscale_snapshot(g: &Grid<f32>) -> ZoneSnapshot {
     let data = g.map(|_, _, v| TileSnapColor::gray(*v));
 
-    ZoneSnapshot { data }
+    ZoneSnapshot { name: "noise", data }
 }
 
 pub fn bool_snapshot(g: &Grid<bool>) -> ZoneSnapshot {
@@ -40,59 +42,238 @@ pub fn bool_snapshot(g: &Grid<bool>) -> ZoneSnapshot {
         false => TileSnapColor::Black,
     });
 
-    ZoneSnapshot { data }
+    ZoneSnapshot { name: "mask", data }
+}
+
+// Noise for one zone, sampled in world coordinates so that it carries on
+// smoothly into the zones next to it. The seed should be the same for
+// every zone in the world.
+pub fn noise_grid(seed: u32, zone_idx: usize, frequency: f32, octaves: u32, lacunarity: f32) -> Grid<f32> {
+    let nz = Perlin::new(seed, frequency, octaves, lacunarity);
+
+    Grid::init_fill(zone_size().0, zone_size().1, |x, y| {
+        let (wx, wy, _) = zone_local_to_world(zone_idx, x, y);
+
+        nz.get(wx as f32, wy as f32)
+    })
 }
 
-pub fn edge_gradient_buffer(buffer: usize, pow: f32) -> Grid<f32> {
-    let mut g = Grid::init(ZONE_SIZE.0, ZONE_SIZE.1, 1.);
+pub fn rand_grid(seed: u32) -> Grid<bool> {
+    let mut rand = Rand::seed(seed as u64);
+
+    Grid::init_fill(zone_size().0, zone_size().1, |_, _| rand.bool(0.5))
+}
 
-    for x in 0..ZONE_SIZE.0 {
-        for z in 0..buffer {
-            let v = (z as f32 / buffer as f32).powf(pow);
+pub fn terrain_snapshot(t: &Grid<Terrain>) -> ZoneSnapshot {
+    let mut data = Grid::init(zone_size().0, zone_size().1, TileSnapColor::White);
 
-            if z < x && z < (ZONE_SIZE.0 - x) {
-                g.set(x, z, v);
-                g.set(x, ZONE_SIZE.1 - z - 1, v);
+    for x in 0..t.width() {
+        for y in 0..t.height() {
+            if let Some(v) = t.get(x, y) {
+                data.set(x, y, TileSnapColor::for_terrain(*v));
             }
         }
     }
 
-    for y in 0..ZONE_SIZE.1 {
-        for z in 0..buffer {
-            let v = (z as f32 / buffer as f32).powf(pow);
+    ZoneSnapshot { name: "terrain", data }
+}
+
+// Find the cheapest path from start to goal, moving in any of the eight
+// directions. The path runs from the goal back to the start.
+pub fn zone_path<C>(start: (usize, usize), goal: (usize, usize), cost: C) -> Option<Vec<(usize, usize)>>
+where
+    C: Fn(usize, usize) -> f32,
+{
+    let result = astar(AStarSettings {
+        start,
+        is_goal: |p| p == goal,
+        cost: |_, (x, y)| cost(x, y),
+        heuristic: |(x, y)| {
+            0.1 * Distance::chebyshev([x as i32, y as i32, 0], [goal.0 as i32, goal.1 as i32, 0])
+        },
+        neighbors: |(x, y)| {
+            let mut n = vec![];
+
+            for nx in x.saturating_sub(1)..=(x + 1).min(zone_size().0 - 1) {
+                for ny in y.saturating_sub(1)..=(y + 1).min(zone_size().1 - 1) {
+                    if (nx, ny) != (x, y) {
+                        n.push((nx, ny));
+                    }
+                }
+            }
+
+            n
+        },
+        max_depth: 10000,
+    });
+
+    result.is_success.then_some(result.path)
+}
 
-            if z <= y && z < (ZONE_SIZE.1 - y) {
-                g.set(z, y, v);
-                g.set(ZONE_SIZE.0 - z - 1, y, v);
+// Run a path between every pair of points. Each path is painted onto the
+// terrain before the next one is found, so later paths can follow earlier
+// ones. `paint` gets the path, from the goal back to the start, and the
+// indices of the two points it joins. `what` names the paths in the log
+// when two points can't be joined.
+pub fn connect_all<C, P>(
+    idx: usize,
+    what: &str,
+    terrain: &mut Grid<Terrain>,
+    points: &[(usize, usize)],
+    cost: C,
+    mut paint: P,
+) where
+    C: Fn(&Grid<Terrain>, usize, usize) -> f32,
+    P: FnMut(&mut Grid<Terrain>, Vec<(usize, usize)>, usize, usize),
+{
+    for (i, p1) in points.iter().enumerate() {
+        for (j, p2) in points.iter().enumerate().skip(i + 1) {
+            match zone_path(*p1, *p2, |x, y| cost(terrain, x, y)) {
+                Some(path) => paint(terrain, path, i, j),
+                None => warn!("could not connect {} from {:?} to {:?} in zone {}", what, p1, p2, idx),
             }
         }
     }
-
-    g
 }
 
-pub fn noise_grid(seed: u32, frequency: f32, octaves: u32, lacunarity: f32) -> Grid<f32> {
-    let mut nz = Perlin::new(seed, frequency, octaves, lacunarity);
+// the eight directions track can run in, clockwise from north
+const TRACK_HEADINGS: [(i32, i32); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+// heading used at the start of the track, when any direction is allowed
+const ANY_HEADING: usize = 8;
 
-    Grid::init_fill(ZONE_SIZE.0, ZONE_SIZE.1, |x, y| nz.get(x as f32, y as f32))
+// the heading track must have when it leaves the zone over an edge tile,
+// so it meets the track of the neighbouring zone head-on
+fn track_exit_heading(x: usize, y: usize) -> Option<usize> {
+    if y == 0 {
+        Some(4)
+    } else if y == zone_size().1 - 1 {
+        Some(0)
+    } else if x == 0 {
+        Some(6)
+    } else if x == zone_size().0 - 1 {
+        Some(2)
+    } else {
+        None
+    }
 }
 
-pub fn rand_grid(seed: u32) -> Grid<bool> {
-    let mut rand = Rand::seed(seed as u64);
+// Find a path for railroad track from start to goal. The heading is part of
+// the search state, so track bends gently, at most 45 degrees per tile, and
+// always meets the zone edges at a right angle.
+pub fn track_path<C>(start: (usize, usize), goal: (usize, usize), cost: C) -> Vec<(usize, usize)>
+where
+    C: Fn(usize, usize) -> f32,
+{
+    let start_heading = track_exit_heading(start.0, start.1)
+        .map(|h| (h + 4) % 8)
+        .unwrap_or(ANY_HEADING);
+    let goal_heading = track_exit_heading(goal.0, goal.1);
 
-    Grid::init_fill(ZONE_SIZE.0, ZONE_SIZE.1, |_, _| rand.bool(0.5))
-}
+    let result = astar(AStarSettings {
+        start: [start.0, start.1, start_heading],
+        is_goal: |[x, y, h]| (x, y) == goal && goal_heading.is_none_or(|g| g == h),
+        cost: |[_, _, a_heading], [x, y, b_heading]| {
+            let (dx, dy) = TRACK_HEADINGS[b_heading];
+            let step = if dx != 0 && dy != 0 { 1.41 } else { 1. };
+            let turn = match a_heading {
+                ANY_HEADING => 0.,
+                _ => match (8 + b_heading - a_heading) % 8 {
+                    0 => 0.,
+                    1 | 7 => 0.5,
+                    _ => 6.,
+                },
+            };
 
-pub fn terrain_snapshot(t: &Grid<Terrain>) -> ZoneSnapshot {
-    let mut data = Grid::init(ZONE_SIZE.0, ZONE_SIZE.1, TileSnapColor::White);
+            step * cost(x, y) + turn
+        },
+        heuristic: |[x, y, _]| {
+            Distance::chebyshev([x as i32, y as i32, 0], [goal.0 as i32, goal.1 as i32, 0])
+        },
+        neighbors: |[x, y, heading]| {
+            let turns: &[usize] = match heading {
+                ANY_HEADING => &[0, 1, 2, 3, 4, 5, 6, 7],
+                _ => &[0, 1, 7, 2, 6],
+            };
 
-    for x in 0..t.width() {
-        for y in 0..t.height() {
-            if let Some(v) = t.get(x, y) {
-                data.set(x, y, TileSnapColor::for_terrain(*v));
+            turns
+                .iter()
+                .filter_map(|t| {
+                    let h = match heading {
+                        ANY_HEADING => *t,
+                        _ => (heading + t) % 8,
+                    };
+                    let (dx, dy) = TRACK_HEADINGS[h];
+                    let nx = x as i32 + dx;
+                    let ny = y as i32 + dy;
+
+                    if nx < 0 || ny < 0 {
+                        return None;
+                    }
+
+                    let (nx, ny) = (nx as usize, ny as usize);
+
+                    // only the goal may be on the edge of the zone
+                    if (nx, ny) != goal
+                        && (nx == 0 || ny == 0 || nx >= zone_size().0 - 1 || ny >= zone_size().1 - 1)
+                    {
+                        return None;
+                    }
+
+                    Some([nx, ny, h])
+                })
+                .collect()
+        },
+        max_depth: 40000,
+    });
+
+    result.path.iter().map(|[x, y, _]| (*x, *y)).collect()
+}
+
+// carve the stairs to the zones above and below. A stair overwrites whatever
+// is beneath it, since the zone on the other side already expects it there.
+pub fn place_stairs(terrain: &mut Grid<Terrain>, constraints: &ZoneConstraints) {
+    let mut place = |stair: &Stair, t: Terrain| {
+        // stairs sit on dry ground
+        for nx in stair.x - 1..=stair.x + 1 {
+            for ny in stair.y - 1..=stair.y + 1 {
+                if terrain.get(nx, ny).is_some_and(|n| n.is_water()) {
+                    terrain.set(nx, ny, Terrain::Dirt);
+                }
             }
         }
+
+        terrain.set(stair.x, stair.y, t);
+    };
+
+    if let Some(stair) = &constraints.stair_up {
+        place(stair, stair.kind.up());
+    }
+
+    if let Some(stair) = &constraints.stair_down {
+        place(stair, stair.kind.down());
     }
+}
 
-    ZoneSnapshot { data }
+// Visit every border tile the overworld has decided for, with whether it
+// can be walked across. Tiles carrying a river, footpath or
+// railroad are left alone, those are continued by the builder itself.
+pub fn apply_edge_masks<F>(constraints: &ZoneConstraints, mut apply: F)
+where
+    F: FnMut(usize, usize, bool),
+{
+    for ((x, y), tile) in constraints.edge_tiles() {
+        if let Some(passable) = tile.passable.filter(|_| tile.features.is_empty()) {
+            apply(x, y, passable);
+        }
+    }
 }
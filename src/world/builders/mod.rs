@@ -1,7 +1,21 @@
+mod cave_zone;
 mod common;
+mod pipeline;
+mod registry;
 mod simple_zone;
+mod steps;
+mod structure;
+mod town_zone;
+mod wfc_zone;
 mod zone_builder;
 
+pub use cave_zone::*;
 pub use common::*;
+pub use pipeline::*;
+pub use registry::*;
 pub use simple_zone::*;
+pub use steps::*;
+pub use structure::*;
+pub use town_zone::*;
+pub use wfc_zone::*;
 pub use zone_builder::*;
@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use crate::{
+    common::{Grid, Rand},
+    projection::zone_size,
+    world::Terrain,
+};
+
+use super::{terrain_snapshot, Lot, ZoneBuilder, ZoneConstraints, ZoneData, ZoneSnapshot, ENABLE_ZONE_SNAPSHOTS};
+
+// Everything a zone is built from, handed from one step of a pipeline to
+// the next.
+pub struct BuildContext {
+    pub idx: usize,
+    // seed of this zone, for steps that need their own rng or noise
+    pub seed: u64,
+    pub rand: Rand,
+    pub constraints: ZoneConstraints,
+    pub terrain: Grid<Terrain>,
+    // noise and masks by name, made by one step and read by later ones
+    pub layers: HashMap<&'static str, Grid<f32>>,
+    pub masks: HashMap<&'static str, Grid<bool>>,
+    // the points rivers (with their width), footpaths and railroads are
+    // carved between
+    pub rivers: Vec<(usize, usize, usize)>,
+    pub footpaths: Vec<(usize, usize)>,
+    pub railroads: Vec<(usize, usize)>,
+    // plots of land set aside for buildings
+    pub lots: Vec<Lot>,
+    // set by a step that can't build the zone it was given
+    pub is_failed: bool,
+}
+
+impl BuildContext {
+    pub fn new(constraints: ZoneConstraints) -> Self {
+        let idx = constraints.idx;
+        let seed = constraints.seed.zone(idx);
+
+        Self {
+            idx,
+            seed,
+            rand: Rand::seed(seed),
+            constraints,
+            terrain: Grid::init(zone_size().0, zone_size().1, Terrain::default()),
+            layers: HashMap::new(),
+            masks: HashMap::new(),
+            rivers: vec![],
+            footpaths: vec![],
+            railroads: vec![],
+            lots: vec![],
+            is_failed: false,
+        }
+    }
+}
+
+// One reusable part of building a zone.
+pub trait BuildStep {
+    fn name(&self) -> &'static str;
+    fn apply(&self, ctx: &mut BuildContext);
+
+    // what the step is shown as in snapshot mode
+    fn snapshot(&self, ctx: &BuildContext) -> ZoneSnapshot {
+        terrain_snapshot(&ctx.terrain)
+    }
+}
+
+// A zone builder put together from an ordered list of steps. A snapshot is
+// recorded after every step, named after it. When a step fails, the zone is
+// built over again with the fallback pipeline, if there is one.
+#[derive(Default)]
+pub struct ZonePipeline {
+    steps: Vec<Box<dyn BuildStep>>,
+    fallback: Option<fn() -> ZonePipeline>,
+    snapshots: Vec<ZoneSnapshot>,
+}
+
+impl ZonePipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn then<S: BuildStep + 'static>(mut self, step: S) -> Self {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    pub fn or_else(mut self, fallback: fn() -> ZonePipeline) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+}
+
+impl ZoneBuilder for ZonePipeline {
+    fn build(&mut self, constraints: ZoneConstraints) -> ZoneData {
+        let mut ctx = BuildContext::new(constraints);
+
+        for step in self.steps.iter() {
+            step.apply(&mut ctx);
+
+            if ENABLE_ZONE_SNAPSHOTS {
+                let mut snapshot = step.snapshot(&ctx);
+                snapshot.name = step.name();
+                self.snapshots.push(snapshot);
+            }
+
+            if let Some(fallback) = self.fallback.filter(|_| ctx.is_failed) {
+                let mut pipeline = fallback();
+                let data = pipeline.build(ctx.constraints);
+
+                self.snapshots.append(&mut pipeline.snapshots);
+
+                return data;
+            }
+        }
+
+        ZoneData {
+            idx: ctx.idx,
+            terrain: ctx.terrain,
+        }
+    }
+
+    fn get_snapshots(&self) -> Vec<ZoneSnapshot> {
+        self.snapshots.to_vec()
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::{
+    projection::zone_xyz,
+    world::{Biome, Map, PoiKind, StructureKind},
+};
+
+use super::{cave_pipeline, surface_pipeline, town_pipeline, wfc_pipeline, ZoneBuilder};
+
+// What a zone is, as far as picking a builder for it goes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ZoneKind {
+    Town,
+    Ruin,
+    // the levels beneath a mine head
+    Mine,
+    Cave,
+    Wilderness(Biome),
+}
+
+impl ZoneKind {
+    pub fn of(map: &Map, idx: usize) -> Self {
+        let (x, y, z) = zone_xyz(idx);
+        let zone = map.get_zone(x, y, z);
+
+        if z > 0 {
+            return match zone.and_then(|o| o.structure) {
+                Some(s) if s.kind == StructureKind::MineHead => ZoneKind::Mine,
+                _ => ZoneKind::Cave,
+            };
+        }
+
+        match zone.and_then(|o| o.poi.as_ref()).map(|p| p.kind) {
+            Some(PoiKind::Town | PoiKind::Capital) => ZoneKind::Town,
+            Some(PoiKind::Ruin) => ZoneKind::Ruin,
+            _ => ZoneKind::Wilderness(map.get_biome(x, y)),
+        }
+    }
+}
+
+type MakeBuilder = fn() -> Box<dyn ZoneBuilder>;
+
+// Every zone builder by name, and which one each kind of zone is built
+// with. A builder can be forced for a single zone, or for all of them, to
+// work on one generator in isolation.
+#[derive(Resource)]
+pub struct ZoneBuilders {
+    builders: HashMap<&'static str, MakeBuilder>,
+    kinds: HashMap<ZoneKind, &'static str>,
+    overrides: HashMap<usize, String>,
+    forced: Option<String>,
+}
+
+impl Default for ZoneBuilders {
+    fn default() -> Self {
+        let mut registry = Self {
+            builders: HashMap::new(),
+            kinds: HashMap::new(),
+            overrides: HashMap::new(),
+            forced: None,
+        };
+
+        registry.register("surface", || Box::new(surface_pipeline()));
+        registry.register("cave", || Box::new(cave_pipeline()));
+        registry.register("town", || Box::new(town_pipeline()));
+        registry.register("wfc", || Box::new(wfc_pipeline()));
+
+        registry.assign(ZoneKind::Town, "town");
+        registry.assign(ZoneKind::Ruin, "wfc");
+        registry.assign(ZoneKind::Wilderness(Biome::Badlands), "wfc");
+        registry.assign(ZoneKind::Wilderness(Biome::Mountain), "wfc");
+        registry.assign(ZoneKind::Mine, "cave");
+        registry.assign(ZoneKind::Cave, "cave");
+
+        registry
+    }
+}
+
+impl ZoneBuilders {
+    pub fn register(&mut self, name: &'static str, make: MakeBuilder) {
+        self.builders.insert(name, make);
+    }
+
+    pub fn assign(&mut self, kind: ZoneKind, name: &'static str) {
+        self.kinds.insert(kind, name);
+    }
+
+    // build the zone with the named builder, whatever kind of zone it is
+    pub fn set_override(&mut self, idx: usize, name: &str) {
+        self.overrides.insert(idx, name.to_string());
+    }
+
+    // whether a builder is forced for the zone, saved zones are built again
+    // when it is
+    pub fn is_overridden(&self, idx: usize) -> bool {
+        self.forced.is_some() || self.overrides.contains_key(&idx)
+    }
+
+    // the names of every builder, sorted
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names = self.builders.keys().copied().collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+
+    // `--builder <name>` on the command line, or the `ZONE_BUILDER` env var,
+    // forces a builder for every zone. `<zone idx>=<name>` forces it for one
+    // zone only, several can be given separated by commas.
+    pub fn with_override(mut self) -> Self {
+        let arg = std::env::args().skip_while(|a| a != "--builder").nth(1);
+
+        let Some(value) = arg.or_else(|| std::env::var("ZONE_BUILDER").ok()) else {
+            return self;
+        };
+
+        for part in value.split(',').map(|p| p.trim()) {
+            match part.split_once('=') {
+                Some((idx, name)) => match idx.parse::<usize>() {
+                    Ok(idx) => self.set_override(idx, name),
+                    Err(_) => warn!("invalid zone builder override '{}'", part),
+                },
+                None => self.forced = Some(part.to_string()),
+            }
+        }
+
+        self
+    }
+
+    // the name of the builder the zone will be built with
+    pub fn pick(&self, map: &Map, idx: usize) -> &str {
+        if let Some(name) = self.overrides.get(&idx).or(self.forced.as_ref()) {
+            if self.builders.contains_key(name.as_str()) {
+                return name;
+            }
+
+            warn!("no zone builder named '{}'", name);
+        }
+
+        let kind = ZoneKind::of(map, idx);
+
+        match (self.kinds.get(&kind), kind) {
+            (Some(name), _) => name,
+            (None, ZoneKind::Mine | ZoneKind::Cave) => "cave",
+            (None, _) => "surface",
+        }
+    }
+
+    pub fn get(&self, map: &Map, idx: usize) -> Box<dyn ZoneBuilder> {
+        let name = self.pick(map, idx);
+
+        info!("building zone {} with {}", idx, name);
+
+        (self.builders[name])()
+    }
+}
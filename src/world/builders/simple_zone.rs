@@ -1,317 +1,451 @@
-use bevy::log::info;
+use bevy::log::warn;
 
 use crate::{
-    common::{astar, remap, AStarSettings, Distance, Grid, Rand},
-    projection::ZONE_SIZE,
+    common::{Distance, Grid, Rand},
+    projection::{zone_local_to_world, zone_size, zone_size_f32},
     world::Terrain,
 };
 
 use super::{
-    bool_snapshot, edge_gradient_buffer, edge_snapshot, noise_grid, rand_grid, terrain_snapshot, ZoneBuilder, ZoneConstraints, ZoneData, ZoneSnapshot, ENABLE_ZONE_SNAPSHOTS
+    apply_edge_masks, connect_all, track_path, BuildContext, BuildStep, BuildStructure, NoiseLayer, PlacePrefabs,
+    PlaceStairs, RandomMask, SeedEdges, Validate, ZoneConstraints, ZonePipeline,
 };
 
-#[derive(Default)]
-pub struct SimpleZoneBuilder {
-    snapshots: Vec<ZoneSnapshot>,
+// rivers at least this wide have a deep channel
+const MAJOR_RIVER_WIDTH: usize = 4;
+// creeks run dry where there is less surface water than this
+const WASH_WATER: f32 = 0.3;
+
+// The surface of the world. The biomes of the zone corners are blended
+// across the zone, then rivers, railroads and roads are carved between the
+// points where they cross its edges, around any structure already built.
+pub fn surface_pipeline() -> ZonePipeline {
+    ZonePipeline::new()
+        .then(ScatterBiome)
+        .then(SeedEdges)
+        .then(CarveLake)
+        .then(BuildStructure)
+        .then(NoiseLayer {
+            name: "height",
+            salt: 13,
+            frequency: 0.1,
+            octaves: 2,
+            lacunarity: 2.,
+        })
+        .then(RandomMask("rough"))
+        .then(CarveRivers)
+        .then(CarveRailroads)
+        .then(CarveFootpaths)
+        .then(MatchEdgeMasks)
+        .then(PlacePrefabs)
+        .then(PlaceFactionMarkers)
+        .then(PlaceStairs)
+        .then(Validate)
 }
 
-impl ZoneBuilder for SimpleZoneBuilder {
-    fn build(&mut self, constraints: ZoneConstraints) -> ZoneData {
-        let idx = constraints.idx;
-        let mut r = Rand::seed(idx as u64);
-        let terrains = vec![Terrain::Grass];
-        let mut terrain = Grid::init_fill(ZONE_SIZE.0, ZONE_SIZE.1, |_, _| r.pick(&terrains));
+// blend the biome of each corner across the zone, with denser plant
+// life in wet climates and bare dirt where water is scarce
+pub struct ScatterBiome;
 
-        let mut rivers = vec![];
-        let mut footpaths = vec![];
+impl BuildStep for ScatterBiome {
+    fn name(&self) -> &'static str {
+        "scatter"
+    }
 
-        if ENABLE_ZONE_SNAPSHOTS {
-            self.snapshots.push(edge_snapshot(&constraints));
-        }
+    fn apply(&self, ctx: &mut BuildContext) {
+        let idx = ctx.idx;
+        let biomes = ctx.constraints.biomes;
+        let climate = &ctx.constraints.climate;
+        let r = &mut ctx.rand;
 
-        for (x, s) in constraints.south.iter().enumerate() {
-            if *s == 1 {
-                rivers.push((x, 0));
-            }
-            if *s == 2 {
-                footpaths.push((x, 0));
+        ctx.terrain = Grid::init_fill(zone_size().0, zone_size().1, |x, y| {
+            let u = x as f32 / (zone_size_f32().0 - 1.);
+            let v = y as f32 / (zone_size_f32().1 - 1.);
+            let biome = biomes.blend(u, v, r.random());
+            let (wx, wy, _) = zone_local_to_world(idx, x, y);
+            let (wx, wy) = (wx as f32, wy as f32);
+
+            let t = biome.pick_terrain(r.random() / climate.vegetation(wx, wy), r.random());
+
+            if t == Terrain::Grass && r.bool(0.5 - climate.water(wx, wy)) {
+                return Terrain::Dirt;
             }
+
+            t
+        });
+    }
+}
+
+pub struct CarveLake;
+
+impl BuildStep for CarveLake {
+    fn name(&self) -> &'static str {
+        "lake"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        if ctx.constraints.is_lake {
+            carve_lake(&mut ctx.terrain, &mut ctx.rand);
         }
+    }
+}
 
-        for (x, n) in constraints.north.iter().enumerate() {
-            if *n == 1 {
-                rivers.push((x, ZONE_SIZE.1 - 1));
+// every river should attempt to connect to every other river,
+// and also follow low ground
+pub struct CarveRivers;
+
+impl BuildStep for CarveRivers {
+    fn name(&self) -> &'static str {
+        "rivers"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let idx = ctx.idx;
+        let height = &ctx.layers["height"];
+        let rough = &ctx.masks["rough"];
+        let climate = &ctx.constraints.climate;
+        let rivers = &ctx.rivers;
+        let points = rivers.iter().map(|r| (r.0, r.1)).collect::<Vec<_>>();
+
+        let cost = |terrain: &Grid<Terrain>, x, y| {
+            let terrain_cost = match terrain.get(x, y).unwrap() {
+                // rivers flow around buildings
+                t if t.is_building() => return 50.,
+                t if t.is_water() => 0.001,
+                _ => 1.0,
+            };
+            let rand_cost = match rough.get(x, y).unwrap() {
+                true => 10.0,
+                false => 1.0,
+            };
+
+            1. + rand_cost * height.get(x, y).unwrap() * terrain_cost
+        };
+
+        connect_all(ctx.idx, "river", &mut ctx.terrain, &points, cost, |terrain, path, i, j| {
+            let (p1, p2) = (rivers[i], rivers[j]);
+
+            // the path runs from p2 back to p1, the width blends
+            // between the two so it matches the neighbouring zones
+            let len = path.len().max(2) - 1;
+
+            for (i, (x, y)) in path.into_iter().enumerate() {
+                let t = i as f32 / len as f32;
+                let width = (p2.2 as f32 * (1. - t) + p1.2 as f32 * t).round() as usize;
+                let (wx, wy, _) = zone_local_to_world(idx, x, y);
+                let is_dry = climate.water(wx as f32, wy as f32) < WASH_WATER;
+
+                carve_river(terrain, x, y, width, is_dry);
             }
-            if *n == 2 {
-                footpaths.push((x, ZONE_SIZE.1 - 1));
+        });
+    }
+}
+
+// every railroad should connect to every other railroad
+pub struct CarveRailroads;
+
+impl BuildStep for CarveRailroads {
+    fn name(&self) -> &'static str {
+        "railroads"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let terrain = &mut ctx.terrain;
+
+        for (p1_idx, p1) in ctx.railroads.iter().enumerate() {
+            for p2 in ctx.railroads.iter().skip(p1_idx + 1) {
+                let path = track_path(*p1, *p2, |x, y| match terrain.get(x, y).unwrap() {
+                    Terrain::Rail => 0.5,
+                    Terrain::Bridge => 1.,
+                    t if t.is_water() => 10.,
+                    Terrain::Tree | Terrain::Cactus | Terrain::Boulder | Terrain::DeadTree => 3.,
+                    t if t.is_building() => 50.,
+                    _ => 1.,
+                });
+
+                if path.is_empty() {
+                    warn!("could not connect railroad from {:?} to {:?} in zone {}", p1, p2, ctx.idx);
+                }
+
+                for (x, y) in path {
+                    // railroads always bridge rivers, so the water keeps flowing
+                    let crossing = match terrain.get(x, y).unwrap() {
+                        t if t.is_building() => continue,
+                        t if t.is_water() => Terrain::Bridge,
+                        _ => Terrain::Rail,
+                    };
+
+                    terrain.set(x, y, crossing);
+                }
             }
         }
 
-        for (y, w) in constraints.west.iter().enumerate() {
-            if *w == 1 {
-                rivers.push((0, y));
-            }
-            if *w == 2 {
-                footpaths.push((0, y));
-            }
+        if ctx.constraints.has_station() {
+            lay_platform(terrain);
         }
+    }
+}
+
+// every footpath should attempt to connect to every other footpath
+pub struct CarveFootpaths;
 
-        for (y, e) in constraints.east.iter().enumerate() {
-            if *e == 1 {
-                rivers.push((ZONE_SIZE.0 - 1, y));
+impl BuildStep for CarveFootpaths {
+    fn name(&self) -> &'static str {
+        "footpaths"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let rough = &ctx.masks["rough"];
+
+        let cost = |terrain: &Grid<Terrain>, x, y| {
+            let rand_cost = match rough.get(x, y).unwrap() {
+                true => 4.0,
+                false => 1.0,
+            };
+
+            let terrain_cost = match terrain.get(x, y).unwrap() {
+                Terrain::Footpath => 0.01,
+                Terrain::Bridge | Terrain::Ford => 0.5,
+                t if t.is_water() => 20.,
+                Terrain::Rail => 4.,
+                Terrain::Tree | Terrain::Cactus | Terrain::Boulder | Terrain::DeadTree => 4.,
+                Terrain::Door => 1.,
+                t if t.is_building() => 50.,
+                _ => 1.,
+            };
+
+            rand_cost * terrain_cost
+        };
+
+        connect_all(ctx.idx, "footpath", &mut ctx.terrain, &ctx.footpaths, cost, |terrain, path, _, _| {
+            for (x, y) in path {
+                match terrain.get(x, y).unwrap() {
+                    // footpaths cross railroads without cutting the track,
+                    // and re-use crossings that are already there
+                    Terrain::Rail | Terrain::Bridge | Terrain::Ford => {}
+                    t if t.is_building() => {}
+                    t if t.is_water() => {
+                        // narrow, shallow rivers can be waded, the rest need a bridge
+                        let is_shallow = !t.is_deep() && river_width(terrain, x, y) <= 2;
+                        let crossing = match is_shallow {
+                            true => Terrain::Ford,
+                            false => Terrain::Bridge,
+                        };
+
+                        terrain.set(x, y, crossing);
+                    }
+                    _ => terrain.set(x, y, Terrain::Footpath),
+                }
             }
-            if *e == 2 {
-                footpaths.push((ZONE_SIZE.0 - 1, y));
+        });
+    }
+}
+
+// fences, tree lines and rock walls carry on from the zones next door. A
+// road or railroad running along the border is kept, even where the zone
+// next door is blocked.
+pub struct MatchEdgeMasks;
+
+impl BuildStep for MatchEdgeMasks {
+    fn name(&self) -> &'static str {
+        "edge masks"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let biomes = ctx.constraints.biomes;
+        let terrain = &mut ctx.terrain;
+
+        apply_edge_masks(&ctx.constraints, |x, y, passable| {
+            let t = *terrain.get(x, y).unwrap();
+            let is_way = matches!(t, Terrain::Footpath | Terrain::Rail | Terrain::Platform | Terrain::Bridge);
+
+            if t.is_solid() == passable && !t.is_water() && !is_way {
+                let u = x as f32 / (zone_size_f32().0 - 1.);
+                let v = y as f32 / (zone_size_f32().1 - 1.);
+                let biome = biomes.blend(u, v, 0.5);
+
+                let t = match passable {
+                    true => biome.ground()[0].0,
+                    false => biome.blocker(),
+                };
+
+                terrain.set(x, y, t);
             }
-        }
+        });
+    }
+}
 
-        if footpaths.len() == 1 {
-            footpaths.push((ZONE_SIZE.0 / 2, ZONE_SIZE.1 / 2));
-        }
+pub struct PlaceFactionMarkers;
 
-        if rivers.len() == 1 {
-            rivers.push((ZONE_SIZE.0 / 2, ZONE_SIZE.1 / 2));
-        }
+impl BuildStep for PlaceFactionMarkers {
+    fn name(&self) -> &'static str {
+        "faction markers"
+    }
 
-        let height = noise_grid(idx as u32, 0.1, 2, 2.);
+    fn apply(&self, ctx: &mut BuildContext) {
+        place_faction_markers(&mut ctx.terrain, &ctx.footpaths, &ctx.constraints);
+    }
+}
 
-        if ENABLE_ZONE_SNAPSHOTS {
-            // self.snapshots.push(grayscale_snapshot(&height));
-        }
+// Carve the river around (x, y). Wide rivers have a deep channel down the
+// middle and shallow banks. A single tile of water is a creek, or a dry wash
+// where there is little surface water. Buildings are left standing.
+fn carve_river(terrain: &mut Grid<Terrain>, x: usize, y: usize, width: usize, is_dry: bool) {
+    if terrain.get(x, y).unwrap().is_building() {
+        return;
+    }
 
-        let edge_buffer = edge_gradient_buffer(8, 1.);
-        let rand_noise = rand_grid(idx as u32);
+    if width <= 1 {
+        let t = match is_dry {
+            true => Terrain::Wash,
+            false => Terrain::Creek,
+        };
 
-        if ENABLE_ZONE_SNAPSHOTS {
-            // self.snapshots.push(grayscale_snapshot(&edge_buffer));
-            // let mul = edge_buffer.map(|x, y, v| {
-            //     remap(1. - v, 0.5, 1.)
-            // });
-            // self.snapshots.push(grayscale_snapshot(&mul));
-            self.snapshots.push(bool_snapshot(&rand_noise));
-        }
+        terrain.set(x, y, terrain.get(x, y).unwrap().deeper(t));
+        return;
+    }
 
-        // every river should attempt to connect to every other river,
-        // and also follow low ground
-        for (p1_idx, p1) in rivers.iter().enumerate() {
-            (p1_idx..rivers.len()).for_each(|p2_idx| {
-                let p2 = rivers[p2_idx];
+    let radius = width as f32 / 2.;
+    let r = radius.ceil() as i32;
+    let channel = match width >= MAJOR_RIVER_WIDTH {
+        true => Terrain::DeepWater,
+        false => Terrain::River,
+    };
 
-                if p1_idx == p2_idx {
-                    return;
-                }
+    for dx in -r..=r {
+        for dy in -r..=r {
+            let (nx, ny) = (x as i32 + dx, y as i32 + dy);
 
-                info!("start {},{}", p1.0, p1.1);
-                info!("goal {},{}", p2.0, p2.1);
-
-                // find path between p1/p2, and set terrain!
-                let result = astar(AStarSettings {
-                    start: [p1.0, p1.1],
-                    is_goal: |p| p[0] == p2.0 && p[1] == p2.1,
-                    cost: |_, b| {
-                        let [x, y] = b;
-                        let h = height.get(x, y).unwrap();
-                        let r = rand_noise.get(x, y).unwrap();
-                        let t = terrain.get(x, y).unwrap();
-                        let e = remap(1. - edge_buffer.get(x, y).unwrap(), 0.25, 1.);
-
-                        let terrain_cost: f32 = match t {
-                            Terrain::Grass => 1.0,
-                            Terrain::Dirt => 1.0,
-                            Terrain::River => 0.001,
-                            Terrain::Footpath => 1.0,
-                        };
-            
-                        let rand_cost = match r {
-                            true => 10.0,
-                            false => 1.0,
-                        };
-                        1. + rand_cost * h * terrain_cost
-                    },
-                    heuristic: |[x, y]| {
-                        0.1 * Distance::chebyshev(
-                            [x as i32, y as i32, 0],
-                            [p2.0 as i32, p2.1 as i32, 0],
-                        )
-                    },
-                    neighbors: |v| {
-                        let mut n = vec![];
-
-                        let [x, y] = v;
-
-                        if x > 0 {
-                            n.push([x - 1, y]);
-
-                            if y > 0 {
-                                n.push([x - 1, y - 1]);
-                            }
-
-                            if y < ZONE_SIZE.1 - 1 {
-                                n.push([x - 1, y + 1]);
-                            }
-                        }
-
-                        if x < ZONE_SIZE.0 - 1 {
-                            n.push([x + 1, y]);
-
-                            if y > 0 {
-                                n.push([x + 1, y - 1]);
-                            }
-
-                            if y < ZONE_SIZE.1 - 1 {
-                                n.push([x + 1, y + 1]);
-                            }
-                        }
-
-                        if y > 0 {
-                            n.push([x, y - 1]);
-                        }
-
-                        if y < ZONE_SIZE.1 - 1 {
-                            n.push([x, y + 1]);
-                        }
-
-                        n
-                    },
-                    max_depth: 10000,
-                });
+            if nx < 0 || ny < 0 || nx >= zone_size().0 as i32 || ny >= zone_size().1 as i32 {
+                continue;
+            }
 
-                if result.is_success {
-                    // info!("path {}", result.path.iter().map(|[a, b, c]| format!("{},{}", a, b)).collect::<Vec<_>>().join(" "));
-                    for [x, y] in result.path {
-                        if x < ZONE_SIZE.0 && y < ZONE_SIZE.1 {
-                            terrain.set(x, y, Terrain::River);
-                        }
-                    }
-                } else {
-                    info!("Failure!");
-                }
-            });
+            let d = ((dx * dx + dy * dy) as f32).sqrt();
+
+            if d > radius {
+                continue;
+            }
+
+            let t = match d <= radius - 1. {
+                true => channel,
+                false => Terrain::ShallowWater,
+            };
+            let (nx, ny) = (nx as usize, ny as usize);
+
+            if terrain.get(nx, ny).unwrap().is_building() {
+                continue;
+            }
+
+            terrain.set(nx, ny, terrain.get(nx, ny).unwrap().deeper(t));
         }
+    }
+}
 
-        // every footpath should attempt to connect to every other footpath
-        for (p1_idx, p1) in footpaths.iter().enumerate() {
-            (p1_idx..footpaths.len()).for_each(|p2_idx| {
-                let p2 = footpaths[p2_idx];
+// carve a rough, roughly elliptical lake in the middle of the zone
+fn carve_lake(terrain: &mut Grid<Terrain>, r: &mut Rand) {
+    let (cx, cy) = (zone_size_f32().0 / 2., zone_size_f32().1 / 2.);
+    let (rx, ry) = (zone_size_f32().0 / 6., zone_size_f32().1 / 4.);
 
-                if p1_idx == p2_idx {
-                    return;
-                }
+    for x in 0..zone_size().0 {
+        for y in 0..zone_size().1 {
+            let dx = (x as f32 - cx) / rx;
+            let dy = (y as f32 - cy) / ry;
 
-                info!("start {},{}", p1.0, p1.1);
-                info!("goal {},{}", p2.0, p2.1);
-
-                // find path between p1/p2, and set terrain!
-                let result = astar(AStarSettings {
-                    start: [p1.0, p1.1],
-                    is_goal: |p| p[0] == p2.0 && p[1] == p2.1,
-                    cost: |a, b| {
-                        let [x, y] = b;
-                        let t = terrain.get(x, y).unwrap();
-                        let h = height.get(x, y).unwrap();
-                        let edge = 1. - edge_buffer.get(x, y).unwrap();
-                        let r = rand_noise.get(x, y).unwrap();
-
-                        // info!("h at {} {} = {}", b[0], b[1], h);
-
-                        let rand_cost = match r {
-                            true => 4.0,
-                            false => 1.0,
-                        };
+            if dx * dx + dy * dy < 1. - r.random() * 0.3 {
+                terrain.set(x, y, Terrain::River);
+            }
+        }
+    }
+}
 
-                        let terrain_cost = match t {
-                            Terrain::Grass => 1.,
-                            Terrain::Dirt => 1.,
-                            Terrain::River => 20.,
-                            Terrain::Footpath => 0.01,
-                        };
+// Factions mark the roads entering their territory, and put up their
+// marker all around their capital.
+fn place_faction_markers(
+    terrain: &mut Grid<Terrain>,
+    footpaths: &[(usize, usize)],
+    constraints: &ZoneConstraints,
+) {
+    let mut markers = vec![];
+
+    for (x, y) in footpaths.iter().copied() {
+        let (dx, dy): (i32, i32) = if y == 0 {
+            (1, 2)
+        } else if y == zone_size().1 - 1 {
+            (1, -2)
+        } else if x == 0 {
+            (2, 1)
+        } else if x == zone_size().0 - 1 {
+            (-2, 1)
+        } else {
+            continue;
+        };
+
+        let u = x as f32 / (zone_size_f32().0 - 1.);
+        let v = y as f32 / (zone_size_f32().1 - 1.);
+        let faction = constraints.factions.nearest(u, v);
+
+        markers.push((x as i32 + dx, y as i32 + dy, faction.marker()));
+    }
 
-                        // if *t == Terrain::Footpath {
-                        //     return 1.;
-                        // }
-
-                        // if *t == Terrain::River {
-                        //     // try to cross at right angles for rivers
-                        //     if a[0] != b[0] && a[1] != b[1] {
-                        //         return 100.;
-                        //     }
-
-                        //     return 10.;
-                        // }
-
-                        rand_cost * terrain_cost
-
-                        // 1. + (h * h * h * 400.) + (edge * edge * 500.)
-                    },
-                    heuristic: |[x, y]| {
-                        0.1 * Distance::chebyshev(
-                            [x as i32, y as i32, 0],
-                            [p2.0 as i32, p2.1 as i32, 0],
-                        )
-                    },
-                    neighbors: |v| {
-                        let mut n = vec![];
-
-                        let [x, y] = v;
-
-                        if x > 0 {
-                            n.push([x - 1, y]);
-
-                            if y > 0 {
-                                n.push([x - 1, y - 1]);
-                            }
-
-                            if y < ZONE_SIZE.1 - 1 {
-                                n.push([x - 1, y + 1]);
-                            }
-                        }
-
-                        if x < ZONE_SIZE.0 - 1 {
-                            n.push([x + 1, y]);
-
-                            if y > 0 {
-                                n.push([x + 1, y - 1]);
-                            }
-
-                            if y < ZONE_SIZE.1 - 1 {
-                                n.push([x + 1, y + 1]);
-                            }
-                        }
-
-                        if y > 0 {
-                            n.push([x, y - 1]);
-                        }
-
-                        if y < ZONE_SIZE.1 - 1 {
-                            n.push([x, y + 1]);
-                        }
-
-                        n
-                    },
-                    max_depth: 10000,
-                });
+    if let Some(faction) = constraints.capital {
+        let (cx, cy) = (zone_size().0 as i32 / 2, zone_size().1 as i32 / 2);
 
-                if result.is_success {
-                    // info!("path {}", result.path.iter().map(|[a, b, c]| format!("{},{}", a, b)).collect::<Vec<_>>().join(" "));
-                    for [x, y] in result.path {
-                        if x < ZONE_SIZE.0 && y < ZONE_SIZE.1 {
-                            terrain.set(x, y, Terrain::Footpath);
-                        }
-                    }
-                } else {
-                    info!("Failure!");
-                }
-            });
+        for (dx, dy) in [(-3, -2), (3, -2), (-3, 2), (3, 2)] {
+            markers.push((cx + dx, cy + dy, faction.marker()));
+        }
+    }
+
+    for (x, y, marker) in markers {
+        if x < 0 || y < 0 || x >= zone_size().0 as i32 || y >= zone_size().1 as i32 {
+            continue;
         }
 
-        if ENABLE_ZONE_SNAPSHOTS {
-            self.snapshots.push(terrain_snapshot(&terrain));
+        let Some(t) = terrain.get(x as usize, y as usize) else {
+            continue;
+        };
+
+        // never block a road, railroad, river or building
+        let is_inside = constraints.structure.is_some_and(|s| s.contains(x as usize, y as usize));
+
+        if is_inside || t.is_water() || t.is_building() || matches!(t, Terrain::Footpath | Terrain::Rail | Terrain::Platform) {
+            continue;
         }
 
-        ZoneData { idx, terrain }
+        terrain.set(x as usize, y as usize, marker);
     }
+}
 
-    fn get_snapshots(&self) -> Vec<ZoneSnapshot> {
-        self.snapshots.to_vec()
+// lay a station platform alongside the track closest to the middle of the zone
+fn lay_platform(terrain: &mut Grid<Terrain>) {
+    let center = [zone_size().0 as i32 / 2, zone_size().1 as i32 / 2, 0];
+    let mut track = vec![];
+
+    for x in 1..zone_size().0 - 1 {
+        for y in 1..zone_size().1 - 2 {
+            if terrain.get(x, y) == Some(&Terrain::Rail) {
+                let d = Distance::chebyshev([x as i32, y as i32, 0], center);
+                track.push((x, y, d));
+            }
+        }
     }
+
+    track.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    for (x, y, _) in track.into_iter().take(6) {
+        if terrain.get(x, y + 1).is_some_and(|t| *t != Terrain::Rail && !t.is_building()) {
+            terrain.set(x, y + 1, Terrain::Platform);
+        }
+    }
+}
+
+// width of the water at (x, y), the shortest horizontal or vertical run
+fn river_width(terrain: &Grid<Terrain>, x: usize, y: usize) -> usize {
+    let is_water = |x: usize, y: usize| terrain.get(x, y).is_some_and(|t| t.is_water());
+
+    let left = (0..x).rev().take_while(|x| is_water(*x, y)).count();
+    let right = (x + 1..zone_size().0).take_while(|x| is_water(*x, y)).count();
+    let down = (0..y).rev().take_while(|y| is_water(x, *y)).count();
+    let up = (y + 1..zone_size().1).take_while(|y| is_water(x, *y)).count();
+
+    (left + right + 1).min(down + up + 1)
 }
@@ -0,0 +1,203 @@
+use bevy::log::{info, warn};
+
+use crate::{
+    projection::{zone_size, zone_xyz},
+    world::{find_prefab_spot, stamp_prefab, EdgeFeatureKind, PrefabTransform, Terrain},
+};
+
+use super::{
+    bool_snapshot, build_structure, edge_snapshot, grayscale_snapshot, noise_grid, place_stairs, rand_grid,
+    BuildContext, BuildStep, ZoneSnapshot,
+};
+
+// Gather the rivers, footpaths and railroads crossing the zone edges, and
+// the points in the middle of the zone they lead to.
+pub struct SeedEdges;
+
+impl BuildStep for SeedEdges {
+    fn name(&self) -> &'static str {
+        "edges"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let c = &ctx.constraints;
+        let center = (zone_size().0 / 2, zone_size().1 / 2);
+
+        for (pos, tile) in c.edge_tiles() {
+            for feature in tile.features.iter() {
+                match feature.kind {
+                    EdgeFeatureKind::River => ctx.rivers.push((pos.0, pos.1, feature.width)),
+                    EdgeFeatureKind::Footpath => ctx.footpaths.push(pos),
+                    EdgeFeatureKind::Railroad => ctx.railroads.push(pos),
+                }
+            }
+        }
+
+        // railroads end at the station in the middle of the zone
+        if c.has_station() || ctx.railroads.len() == 1 {
+            ctx.railroads.push(center);
+        }
+
+        // roads lead to the point of interest in the middle of the zone
+        if c.poi.is_some() || ctx.footpaths.len() == 1 {
+            ctx.footpaths.push(center);
+        }
+
+        // a river that springs up or drains into a lake in this
+        // zone runs from (or to) the middle of it
+        let widest = ctx.rivers.iter().map(|r| r.2).max().unwrap_or(1);
+
+        if c.is_lake || ctx.rivers.len() == 1 {
+            ctx.rivers.push((center.0, center.1, widest));
+        }
+    }
+
+    fn snapshot(&self, ctx: &BuildContext) -> ZoneSnapshot {
+        edge_snapshot(&ctx.constraints)
+    }
+}
+
+// A world-space noise layer, read by later steps by its name. The salt
+// keeps it apart from the other noise in the world.
+pub struct NoiseLayer {
+    pub name: &'static str,
+    pub salt: u64,
+    pub frequency: f32,
+    pub octaves: u32,
+    pub lacunarity: f32,
+}
+
+impl BuildStep for NoiseLayer {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let seed = ctx.constraints.seed.mix(self.salt) as u32;
+        let grid = noise_grid(seed, ctx.idx, self.frequency, self.octaves, self.lacunarity);
+
+        ctx.layers.insert(self.name, grid);
+    }
+
+    fn snapshot(&self, ctx: &BuildContext) -> ZoneSnapshot {
+        grayscale_snapshot(&ctx.layers[self.name])
+    }
+}
+
+// a coin toss for every tile, used to roughen up paths
+pub struct RandomMask(pub &'static str);
+
+impl BuildStep for RandomMask {
+    fn name(&self) -> &'static str {
+        self.0
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        ctx.masks.insert(self.0, rand_grid(ctx.seed as u32));
+    }
+
+    fn snapshot(&self, ctx: &BuildContext) -> ZoneSnapshot {
+        bool_snapshot(&ctx.masks[self.0])
+    }
+}
+
+// the floor of any structure reaching through this zone
+pub struct BuildStructure;
+
+impl BuildStep for BuildStructure {
+    fn name(&self) -> &'static str {
+        "structure"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let Some(structure) = &ctx.constraints.structure else {
+            return;
+        };
+
+        let z = zone_xyz(ctx.idx).2;
+
+        build_structure(&mut ctx.terrain, structure, z);
+
+        // on the surface, the road ends right outside the door
+        if z == 0 {
+            let (dx, dy) = structure.door();
+            ctx.terrain.set(dx, dy - 1, Terrain::Footpath);
+        }
+    }
+}
+
+pub struct PlaceStairs;
+
+impl BuildStep for PlaceStairs {
+    fn name(&self) -> &'static str {
+        "stairs"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        place_stairs(&mut ctx.terrain, &ctx.constraints);
+    }
+}
+
+// Check the finished zone still keeps its promises to the rest of the
+// world: the stairs are where the zones above and below expect them, and
+// nothing solid sits where a feature crosses the edge.
+pub struct Validate;
+
+impl BuildStep for Validate {
+    fn name(&self) -> &'static str {
+        "validate"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let c = &ctx.constraints;
+        let terrain_at = |x: usize, y: usize| *ctx.terrain.get(x, y).unwrap();
+
+        if let Some(stair) = c.stair_down.filter(|s| !terrain_at(s.x, s.y).is_way_down()) {
+            warn!("zone {} is missing its way down at {},{}", ctx.idx, stair.x, stair.y);
+        }
+
+        if let Some(stair) = c.stair_up.filter(|s| !terrain_at(s.x, s.y).is_way_up()) {
+            warn!("zone {} is missing its way up at {},{}", ctx.idx, stair.x, stair.y);
+        }
+
+        for ((x, y), tile) in c.edge_tiles() {
+            if !tile.features.is_empty() && terrain_at(x, y).is_solid() {
+                warn!("zone {} blocks the edge crossing at {},{}", ctx.idx, x, y);
+            }
+        }
+    }
+}
+
+// Hand-drawn rooms and landmarks, each placed with its own chance in the
+// zones that suit it, turned and mirrored at random.
+pub struct PlacePrefabs;
+
+impl BuildStep for PlacePrefabs {
+    fn name(&self) -> &'static str {
+        "prefabs"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let c = &ctx.constraints;
+        let biome = c.biomes.blend(0.5, 0.5, 0.5);
+        let poi = c.poi.as_ref().map(|p| p.kind);
+        let stairs = [c.stair_up, c.stair_down].iter().flatten().map(|s| (s.x, s.y)).collect::<Vec<_>>();
+
+        // sorted, so the same zone always gets the same prefabs
+        let mut prefabs = c.prefabs.iter().filter(|(_, p)| p.fits(biome, poi)).collect::<Vec<_>>();
+        prefabs.sort_by_key(|(name, _)| *name);
+
+        for (name, prefab) in prefabs {
+            if !ctx.rand.bool(prefab.chance) {
+                continue;
+            }
+
+            let grid = prefab.grid(PrefabTransform::random(&mut ctx.rand));
+
+            match find_prefab_spot(&ctx.terrain, &grid, &stairs, &mut ctx.rand) {
+                Some((x, y)) => stamp_prefab(&mut ctx.terrain, &grid, x, y),
+                None => info!("no room for prefab {} in zone {}", name, ctx.idx),
+            }
+        }
+    }
+}
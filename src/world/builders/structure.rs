@@ -0,0 +1,49 @@
+use crate::{
+    common::Grid,
+    world::{Structure, StructureKind, Terrain},
+};
+
+// Draw one level of a structure. The outer walls and the door are the same
+// on every level, only the rooms inside differ.
+pub fn build_structure(terrain: &mut Grid<Terrain>, structure: &Structure, level: usize) {
+    for x in structure.x..structure.x + structure.width {
+        for y in structure.y..structure.y + structure.height {
+            let t = match structure.is_wall(x, y) {
+                true => Terrain::Wall,
+                false => Terrain::Floor,
+            };
+
+            terrain.set(x, y, t);
+        }
+    }
+
+    let (dx, dy) = structure.door();
+    terrain.set(dx, dy, Terrain::Door);
+
+    match (structure.kind, level) {
+        (StructureKind::Saloon, 0) => build_bar(terrain, structure),
+        (StructureKind::Bank, 1) => build_vault(terrain, structure),
+        _ => {}
+    }
+}
+
+// a counter along the west half of the saloon
+fn build_bar(terrain: &mut Grid<Terrain>, structure: &Structure) {
+    let y = structure.y + structure.height - 3;
+
+    for x in structure.x + 1..structure.x + structure.width / 2 {
+        terrain.set(x, y, Terrain::Counter);
+    }
+}
+
+// a small locked room in the east half of the bank basement
+fn build_vault(terrain: &mut Grid<Terrain>, structure: &Structure) {
+    let vx = structure.x + structure.width / 2 + 1;
+    let top = structure.y + structure.height - 1;
+
+    for y in structure.y..=top {
+        terrain.set(vx, y, Terrain::Wall);
+    }
+
+    terrain.set(vx, structure.y + 2, Terrain::Door);
+}
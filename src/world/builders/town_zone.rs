@@ -0,0 +1,474 @@
+use bevy::log::warn;
+
+use crate::{
+    common::{Distance, Grid},
+    projection::zone_size,
+    world::{StructureKind, Terrain},
+};
+
+use super::{
+    surface_pipeline, BuildContext, BuildStep, BuildStructure, CarveFootpaths, CarveRailroads, CarveRivers,
+    MatchEdgeMasks, NoiseLayer, PlaceFactionMarkers, PlaceStairs, RandomMask, ScatterBiome, SeedEdges, Validate,
+    ZonePipeline,
+};
+
+// how deep a lot reaches back from the street, and how wide it can be
+const LOT_DEPTH: usize = 6;
+const LOT_MIN_DEPTH: usize = 4;
+const LOT_MIN_WIDTH: usize = 5;
+const LOT_MAX_WIDTH: usize = 8;
+// walls have a window every this many tiles
+const WINDOW_SPACING: usize = 3;
+
+// A town with a main street running along the road into it, lots on both
+// sides of the street, and a building on every lot. The structure in the
+// middle of town goes up first, so everything after is built around it.
+// Where there is no room for every building a town needs, the zone is
+// built as plain surface instead.
+pub fn town_pipeline() -> ZonePipeline {
+    ZonePipeline::new()
+        .then(ScatterBiome)
+        .then(SeedEdges)
+        .then(BuildStructure)
+        .then(NoiseLayer {
+            name: "height",
+            salt: 13,
+            frequency: 0.1,
+            octaves: 2,
+            lacunarity: 2.,
+        })
+        .then(RandomMask("rough"))
+        .then(CarveRivers)
+        .then(CarveRailroads)
+        .then(LayStreet)
+        .then(RaiseBuildings)
+        .then(CarveFootpaths)
+        .then(MatchEdgeMasks)
+        .then(PlaceFactionMarkers)
+        .then(PlaceStairs)
+        .then(Validate)
+        .or_else(surface_pipeline)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BuildingKind {
+    Saloon,
+    GeneralStore,
+    Sheriff,
+    Stables,
+    House,
+}
+
+// A plot of land along a street, with the door of its building facing the
+// street.
+#[derive(Clone, Copy)]
+pub struct Lot {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    pub door: (usize, usize),
+}
+
+impl Lot {
+    fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    fn is_wall(&self, x: usize, y: usize) -> bool {
+        x == self.x || y == self.y || x == self.x + self.width - 1 || y == self.y + self.height - 1
+    }
+
+    // the step from the door into the building
+    fn inward(&self) -> (i32, i32) {
+        let (dx, dy) = self.door;
+
+        if dy == self.y + self.height - 1 {
+            (0, -1)
+        } else if dy == self.y {
+            (0, 1)
+        } else if dx == self.x {
+            (1, 0)
+        } else {
+            (-1, 0)
+        }
+    }
+
+    // the row of floor, just inside the wall, the given number of tiles
+    // in from the door
+    fn row(&self, depth: usize) -> Vec<(usize, usize)> {
+        let (ix, iy) = self.inward();
+        let (dx, dy) = (self.door.0 as i32 + ix * depth as i32, self.door.1 as i32 + iy * depth as i32);
+
+        let tiles: Vec<(i32, i32)> = match ix {
+            0 => (self.x + 1..self.x + self.width - 1).map(|x| (x as i32, dy)).collect(),
+            _ => (self.y + 1..self.y + self.height - 1).map(|y| (dx, y as i32)).collect(),
+        };
+
+        tiles
+            .into_iter()
+            .filter(|(x, y)| *x >= 0 && *y >= 0)
+            .map(|(x, y)| (x as usize, y as usize))
+            .filter(|(x, y)| self.contains(*x, *y) && !self.is_wall(*x, *y))
+            .collect()
+    }
+
+    // how many rows of floor there are between the door and the back wall
+    fn depth(&self) -> usize {
+        match self.inward().0 {
+            0 => self.height - 2,
+            _ => self.width - 2,
+        }
+    }
+}
+
+// The main street follows the roads into town, along the axis most of them
+// come in on, and runs past any structure rather than through it. Lots are
+// laid out along both sides. When that leaves no room for every building a
+// town needs, the street runs the other way instead.
+pub struct LayStreet;
+
+impl BuildStep for LayStreet {
+    fn name(&self) -> &'static str {
+        "street"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let (w, h) = zone_size();
+        // roads coming in from the west or east, and from the south or north
+        let is_side_entry = |(x, _): &(usize, usize)| *x == 0 || *x == w - 1;
+        let is_end_entry = |p: &(usize, usize)| (p.1 == 0 || p.1 == h - 1) && !is_side_entry(p);
+
+        let side_entries = ctx.footpaths.iter().filter(|p| is_side_entry(p)).count();
+        let end_entries = ctx.footpaths.iter().filter(|p| is_end_entry(p)).count();
+        let is_horizontal = side_entries >= end_entries;
+        let needed = required_buildings(ctx).len();
+
+        let mut best: Option<(bool, usize, Vec<Lot>)> = None;
+
+        for is_horizontal in [is_horizontal, !is_horizontal] {
+            let Some((at, lots)) = plan_street(ctx, is_horizontal) else {
+                continue;
+            };
+
+            if best.as_ref().is_none_or(|b| lots.len() > b.2.len()) {
+                best = Some((is_horizontal, at, lots));
+            }
+
+            if best.as_ref().is_some_and(|b| b.2.len() >= needed) {
+                break;
+            }
+        }
+
+        let Some((is_horizontal, at, lots)) = best else {
+            warn!("no room for a main street in zone {}", ctx.idx);
+            return;
+        };
+
+        let along_len = match is_horizontal {
+            true => w,
+            false => h,
+        };
+
+        for a in 1..along_len - 1 {
+            for c in at - 1..=at + 1 {
+                let (x, y) = street_xy(is_horizontal, a, c);
+                let t = *ctx.terrain.get(x, y).unwrap();
+
+                let street = match t {
+                    Terrain::Rail | Terrain::Bridge | Terrain::Platform => t,
+                    t if t.is_water() => Terrain::Bridge,
+                    _ => Terrain::Footpath,
+                };
+
+                ctx.terrain.set(x, y, street);
+            }
+        }
+
+        ctx.lots = lots;
+    }
+}
+
+// positions are worked out along the street and across it, then turned
+// into zone coordinates
+fn street_xy(is_horizontal: bool, a: usize, c: usize) -> (usize, usize) {
+    match is_horizontal {
+        true => (a, c),
+        false => (c, a),
+    }
+}
+
+// Where the street would go, and the lots along both sides of it. Lots
+// shrink down to the smallest a building can be to fit between whatever is
+// in the way.
+fn plan_street(ctx: &mut BuildContext, is_horizontal: bool) -> Option<(usize, Vec<Lot>)> {
+    let (w, h) = zone_size();
+    let (along_len, across_len) = match is_horizontal {
+        true => (w, h),
+        false => (h, w),
+    };
+
+    let entry = ctx
+        .footpaths
+        .iter()
+        .find(|(x, y)| match is_horizontal {
+            true => *x == 0 || *x == w - 1,
+            false => (*y == 0 || *y == h - 1) && *x != 0 && *x != w - 1,
+        })
+        .map(|(x, y)| if is_horizontal { *y } else { *x })
+        .unwrap_or(across_len / 2 - 1);
+
+    let at = street_position(ctx, entry, across_len, is_horizontal)?;
+    let mut lots = vec![];
+
+    // lots face the street from both sides, with an alley between each
+    for side in [false, true] {
+        let depth = match side {
+            false => (at - 2).min(LOT_DEPTH),
+            true => (across_len - at - 3).min(LOT_DEPTH),
+        };
+
+        if depth < LOT_MIN_DEPTH {
+            continue;
+        }
+
+        let (c0, c1, door_c) = match side {
+            false => (at - 1 - depth, at - 2, at - 2),
+            true => (at + 2, at + 1 + depth, at + 2),
+        };
+
+        let make_lot = |a: usize, width: usize| {
+            let (x0, y0) = street_xy(is_horizontal, a, c0);
+            let (x1, y1) = street_xy(is_horizontal, a + width - 1, c1);
+
+            Lot {
+                x: x0,
+                y: y0,
+                width: x1 - x0 + 1,
+                height: y1 - y0 + 1,
+                door: street_xy(is_horizontal, a + width / 2, door_c),
+            }
+        };
+
+        let mut a = 2;
+
+        while a + LOT_MIN_WIDTH <= along_len - 2 {
+            let wanted = (ctx.rand.range_n(LOT_MIN_WIDTH as i32, LOT_MAX_WIDTH as i32 + 1) as usize)
+                .min(along_len - 2 - a);
+
+            let lot = (LOT_MIN_WIDTH..=wanted)
+                .rev()
+                .map(|width| (width, make_lot(a, width)))
+                .find(|(_, lot)| is_clear(ctx, lot));
+
+            match lot {
+                Some((width, lot)) => {
+                    lots.push(lot);
+                    a += width + 1;
+                }
+                None => a += 1,
+            }
+        }
+    }
+
+    Some((at, lots))
+}
+
+// Where the middle of the street goes across the zone, as close to the road
+// coming in as it can be while staying clear of the structure.
+fn street_position(ctx: &BuildContext, entry: usize, across_len: usize, is_horizontal: bool) -> Option<usize> {
+    let lowest = 2;
+    let highest = across_len - 3;
+
+    let Some(s) = ctx.constraints.structure else {
+        return Some(entry.clamp(lowest + LOT_MIN_DEPTH, highest - LOT_MIN_DEPTH));
+    };
+
+    // the street stays a tile clear of the structure walls
+    let (s0, s1) = match is_horizontal {
+        true => (s.y, s.y + s.height - 1),
+        false => (s.x, s.x + s.width - 1),
+    };
+
+    let below = s0.checked_sub(3).filter(|at| *at >= lowest);
+    let above = Some(s1 + 3).filter(|at| *at <= highest);
+
+    [below, above]
+        .into_iter()
+        .flatten()
+        .min_by_key(|at| at.abs_diff(entry))
+}
+
+// nothing is built over water, track, the structure or the middle of town
+fn is_clear(ctx: &BuildContext, lot: &Lot) -> bool {
+    let center = (zone_size().0 / 2, zone_size().1 / 2);
+
+    if lot.contains(center.0, center.1) {
+        return false;
+    }
+
+    (lot.x..lot.x + lot.width).all(|x| {
+        (lot.y..lot.y + lot.height).all(|y| {
+            let t = ctx.terrain.get(x, y).unwrap();
+            let near_structure = ctx.constraints.structure.is_some_and(|s| {
+                x + 1 >= s.x && x <= s.x + s.width && y + 1 >= s.y && y <= s.y + s.height
+            });
+
+            !near_structure && !t.is_water() && !matches!(t, Terrain::Rail | Terrain::Platform)
+        })
+    })
+}
+
+// Every town has a saloon, a general store, a sheriff and stables, on the
+// lots closest to the middle of town. The rest of the lots are houses.
+pub struct RaiseBuildings;
+
+impl BuildStep for RaiseBuildings {
+    fn name(&self) -> &'static str {
+        "buildings"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let center = [zone_size().0 as i32 / 2, zone_size().1 as i32 / 2, 0];
+        let mut lots = ctx.lots.clone();
+
+        lots.sort_by(|a, b| {
+            let da = Distance::chebyshev([a.door.0 as i32, a.door.1 as i32, 0], center);
+            let db = Distance::chebyshev([b.door.0 as i32, b.door.1 as i32, 0], center);
+
+            da.total_cmp(&db)
+        });
+
+        let required = required_buildings(ctx);
+
+        // a town without its saloon or sheriff is no town at all
+        if lots.len() < required.len() {
+            warn!("town in zone {} only has room for {} of its buildings", ctx.idx, lots.len());
+            ctx.is_failed = true;
+            return;
+        }
+
+        for (i, lot) in lots.iter().enumerate() {
+            let kind = required.get(i).copied().unwrap_or(BuildingKind::House);
+
+            build_building(&mut ctx.terrain, lot, kind);
+        }
+    }
+}
+
+// the buildings every town has, most important first. The structure in the
+// middle of town may already be the saloon.
+fn required_buildings(ctx: &BuildContext) -> Vec<BuildingKind> {
+    let has_saloon = ctx.constraints.structure.is_some_and(|s| s.kind == StructureKind::Saloon);
+    let mut required = vec![BuildingKind::GeneralStore, BuildingKind::Sheriff, BuildingKind::Stables];
+
+    if !has_saloon {
+        required.insert(0, BuildingKind::Saloon);
+    }
+
+    required
+}
+
+fn build_building(terrain: &mut Grid<Terrain>, lot: &Lot, kind: BuildingKind) {
+    let floor = match kind {
+        BuildingKind::Stables => Terrain::Dirt,
+        _ => Terrain::Floor,
+    };
+
+    for x in lot.x..lot.x + lot.width {
+        for y in lot.y..lot.y + lot.height {
+            let t = match lot.is_wall(x, y) {
+                true => wall_at(lot, x, y, kind),
+                false => floor,
+            };
+
+            terrain.set(x, y, t);
+        }
+    }
+
+    terrain.set(lot.door.0, lot.door.1, Terrain::Door);
+
+    let depth = lot.depth();
+    let back = lot.row(depth);
+
+    match kind {
+        // a bar across the back of the room, with a gap to get behind it
+        BuildingKind::Saloon => {
+            let bar = match depth {
+                0..=2 => depth,
+                _ => depth - 1,
+            };
+
+            for (x, y) in lot.row(bar).into_iter().skip(1) {
+                terrain.set(x, y, Terrain::Counter);
+            }
+        }
+        // shelves on the back wall, and a counter in front of them
+        BuildingKind::GeneralStore => {
+            for (x, y) in back.iter() {
+                terrain.set(*x, *y, Terrain::Counter);
+            }
+
+            if depth >= 4 {
+                let counter = lot.row(depth - 2);
+
+                for (x, y) in counter.iter().skip(1).take(counter.len().saturating_sub(2)) {
+                    terrain.set(*x, *y, Terrain::Counter);
+                }
+            }
+        }
+        // cells barred off at the back, and a desk by the door
+        BuildingKind::Sheriff => {
+            for (i, (x, y)) in back.iter().enumerate() {
+                if i % 2 == 0 {
+                    terrain.set(*x, *y, Terrain::Fence);
+                }
+            }
+
+            if let Some((x, y)) = lot.row(1).first() {
+                terrain.set(*x, *y, Terrain::Counter);
+            }
+        }
+        // stalls along the back, and a wide doorway for the horses
+        BuildingKind::Stables => {
+            for (i, (x, y)) in back.iter().enumerate() {
+                if i % 2 == 1 {
+                    terrain.set(*x, *y, Terrain::Fence);
+                }
+            }
+
+            // lots are at least five wide, so the door is never next
+            // to a corner
+            let (dx, dy) = lot.door;
+            let beside = match lot.inward().0 {
+                0 => [(dx - 1, dy), (dx + 1, dy)],
+                _ => [(dx, dy - 1), (dx, dy + 1)],
+            };
+
+            for (x, y) in beside {
+                terrain.set(x, y, Terrain::Dirt);
+            }
+        }
+        BuildingKind::House => {}
+    }
+}
+
+// walls have windows spaced along them, but never on the corners. The
+// stables don't have any.
+fn wall_at(lot: &Lot, x: usize, y: usize, kind: BuildingKind) -> Terrain {
+    let is_corner = (x == lot.x || x == lot.x + lot.width - 1) && (y == lot.y || y == lot.y + lot.height - 1);
+
+    if is_corner || kind == BuildingKind::Stables {
+        return Terrain::Wall;
+    }
+
+    let along = match y == lot.y || y == lot.y + lot.height - 1 {
+        true => x - lot.x,
+        false => y - lot.y,
+    };
+
+    match along % WINDOW_SPACING == 2 {
+        true => Terrain::Window,
+        false => Terrain::Wall,
+    }
+}
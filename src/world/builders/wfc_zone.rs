@@ -0,0 +1,338 @@
+use bevy::log::{info, warn};
+
+use crate::{
+    common::{Grid, Rand},
+    projection::{zone_size, zone_size_f32},
+    world::{Sample, Terrain},
+};
+
+use super::{
+    connect_caverns, BuildContext, BuildStep, BuildStructure, CarveFootpaths, CarveLake, CarveRailroads, CarveRivers,
+    MatchEdgeMasks, NoiseLayer, PlaceFactionMarkers, PlacePrefabs, PlaceStairs, RandomMask, ScatterBiome, SeedEdges,
+    Validate, ZoneConstraints, ZonePipeline,
+};
+
+// how many times the wave is started over after it runs into a tile
+// nothing can go in
+const WFC_TRIES: usize = 10;
+
+// east, north, west, south
+const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+
+// Ground grown with wave function collapse from a small hand-drawn sample,
+// for badlands, canyons and ruins. The zone edges are settled first so the
+// result lines up with the zones next door, and any structure is built
+// before rivers, railroads and roads are carved around it as on the rest of
+// the surface.
+pub fn wfc_pipeline() -> ZonePipeline {
+    ZonePipeline::new()
+        .then(CollapseSample)
+        .then(SeedEdges)
+        .then(CarveLake)
+        .then(BuildStructure)
+        .then(ConnectOpenGround)
+        .then(NoiseLayer {
+            name: "height",
+            salt: 13,
+            frequency: 0.1,
+            octaves: 2,
+            lacunarity: 2.,
+        })
+        .then(RandomMask("rough"))
+        .then(CarveRivers)
+        .then(CarveRailroads)
+        .then(CarveFootpaths)
+        .then(MatchEdgeMasks)
+        .then(PlacePrefabs)
+        .then(PlaceFactionMarkers)
+        .then(PlaceStairs)
+        .then(Validate)
+}
+
+// Which terrain may sit next to which, learned from a sample. Every tile
+// is a bit, so a tile of the wave is the set of terrain it could still be.
+pub struct WfcRules {
+    tiles: Vec<Terrain>,
+    // how often each tile shows up in the sample
+    weights: Vec<f32>,
+    // for each direction and tile, the tiles seen next to it that way
+    allowed: [Vec<u64>; 4],
+}
+
+impl WfcRules {
+    pub fn learn(sample: &Sample) -> Self {
+        let mut rules = Self {
+            tiles: vec![],
+            weights: vec![],
+            allowed: Default::default(),
+        };
+
+        for grid in sample.grids() {
+            for t in grid.iter().flatten() {
+                match rules.tiles.iter().position(|o| o == t) {
+                    Some(i) => rules.weights[i] += 1.,
+                    None if rules.tiles.len() < 64 => {
+                        rules.tiles.push(*t);
+                        rules.weights.push(1.);
+                        rules.allowed.iter_mut().for_each(|a| a.push(0));
+                    }
+                    None => warn!("sample has more than 64 kinds of terrain, the rest are left out"),
+                }
+            }
+
+            for x in 0..grid.width() {
+                for y in 0..grid.height() {
+                    let Some(i) = grid.get(x, y).unwrap().and_then(|t| rules.tile(t)) else {
+                        continue;
+                    };
+
+                    for (d, (dx, dy)) in DIRECTIONS.iter().enumerate() {
+                        let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+
+                        if nx < 0 || ny < 0 || nx >= grid.width() as i32 || ny >= grid.height() as i32 {
+                            continue;
+                        }
+
+                        if let Some(j) = grid.get(nx as usize, ny as usize).unwrap().and_then(|t| rules.tile(t)) {
+                            rules.allowed[d][i] |= 1 << j;
+                        }
+                    }
+                }
+            }
+        }
+
+        rules
+    }
+
+    fn tile(&self, t: Terrain) -> Option<usize> {
+        self.tiles.iter().position(|o| *o == t)
+    }
+
+    // every tile there is
+    fn all(&self) -> u64 {
+        match self.tiles.len() {
+            64 => u64::MAX,
+            n => (1 << n) - 1,
+        }
+    }
+
+    fn matching<F: Fn(Terrain) -> bool>(&self, f: F) -> u64 {
+        self.tiles
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| f(**t))
+            .fold(0, |mask, (i, _)| mask | 1 << i)
+    }
+
+    // the tiles that may sit in direction `d` of a tile that could be any
+    // of `options`
+    fn neighbors(&self, options: u64, d: usize) -> u64 {
+        (0..self.tiles.len())
+            .filter(|i| options & 1 << i != 0)
+            .fold(0, |mask, i| mask | self.allowed[d][i])
+    }
+
+    // The wave before anything is picked. Edges the zones next door need
+    // to be solid only get solid terrain, and edges that have to be crossed
+    // only get open ground, as far as the sample allows.
+    pub fn edge_wave(&self, constraints: &ZoneConstraints) -> Grid<u64> {
+        let solid = self.matching(|t| t.is_solid());
+        let open = self.matching(|t| !t.is_solid());
+        let mut wave = Grid::init(zone_size().0, zone_size().1, self.all());
+
+        for ((x, y), tile) in constraints.edge_tiles() {
+            let mask = match (tile.passable, tile.features.is_empty()) {
+                (Some(false), _) => solid,
+                (Some(true), _) | (None, false) => open,
+                (None, true) => continue,
+            };
+
+            let options = *wave.get(x, y).unwrap() & mask;
+
+            if options != 0 {
+                wave.set(x, y, options);
+            }
+        }
+
+        wave
+    }
+
+    // Collapse the wave one tile at a time, always the one with the fewest
+    // options left. None when it runs into a tile nothing can go in.
+    pub fn collapse(&self, wave: &Grid<u64>, r: &mut Rand) -> Option<Grid<Terrain>> {
+        if self.tiles.is_empty() {
+            return None;
+        }
+
+        let mut wave = wave.clone();
+        let (w, h) = (wave.width(), wave.height());
+
+        // breaks ties between tiles with as many options left
+        let ties = Grid::init_fill(w, h, |_, _| r.random() * 0.5);
+
+        let settled = (0..w)
+            .flat_map(|x| (0..h).map(move |y| (x, y)))
+            .filter(|(x, y)| *wave.get(*x, *y).unwrap() != self.all())
+            .collect();
+
+        if !self.propagate(&mut wave, settled) {
+            return None;
+        }
+
+        loop {
+            let mut next = None;
+            let mut lowest = f32::MAX;
+
+            for x in 0..w {
+                for y in 0..h {
+                    let count = wave.get(x, y).unwrap().count_ones();
+                    let score = count as f32 + ties.get(x, y).unwrap();
+
+                    if count > 1 && score < lowest {
+                        lowest = score;
+                        next = Some((x, y));
+                    }
+                }
+            }
+
+            let Some((x, y)) = next else {
+                break;
+            };
+
+            let tile = self.pick(*wave.get(x, y).unwrap(), r);
+            wave.set(x, y, 1 << tile);
+
+            if !self.propagate(&mut wave, vec![(x, y)]) {
+                return None;
+            }
+        }
+
+        Some(wave.map(|_, _, options| self.tiles[options.trailing_zeros() as usize]))
+    }
+
+    // one of the options, weighted by how often it shows up in the sample
+    fn pick(&self, options: u64, r: &mut Rand) -> usize {
+        let candidates = (0..self.tiles.len()).filter(|i| options & 1 << i != 0).collect::<Vec<_>>();
+        let total = candidates.iter().map(|i| self.weights[*i]).sum::<f32>();
+        let mut roll = r.random() * total;
+
+        for i in candidates.iter() {
+            roll -= self.weights[*i];
+
+            if roll <= 0. {
+                return *i;
+            }
+        }
+
+        *candidates.last().unwrap()
+    }
+
+    // narrow down the neighbours of every changed tile, and theirs in turn,
+    // false when a tile is left with no options
+    fn propagate(&self, wave: &mut Grid<u64>, mut changed: Vec<(usize, usize)>) -> bool {
+        let (w, h) = (wave.width() as i32, wave.height() as i32);
+
+        while let Some((x, y)) = changed.pop() {
+            let options = *wave.get(x, y).unwrap();
+
+            for (d, (dx, dy)) in DIRECTIONS.iter().enumerate() {
+                let (nx, ny) = (x as i32 + dx, y as i32 + dy);
+
+                if nx < 0 || ny < 0 || nx >= w || ny >= h {
+                    continue;
+                }
+
+                let (nx, ny) = (nx as usize, ny as usize);
+                let before = *wave.get(nx, ny).unwrap();
+                let after = before & self.neighbors(options, d);
+
+                if after == before {
+                    continue;
+                }
+
+                if after == 0 {
+                    return false;
+                }
+
+                wave.set(nx, ny, after);
+                changed.push((nx, ny));
+            }
+        }
+
+        true
+    }
+}
+
+// Grow the ground from the sample that suits the zone. Samples made for its
+// point of interest are picked over the ones for its biome. When none fit,
+// or the wave keeps failing, the biome is scattered like anywhere else.
+pub struct CollapseSample;
+
+impl BuildStep for CollapseSample {
+    fn name(&self) -> &'static str {
+        "collapse"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let c = &ctx.constraints;
+        let biome = c.biomes.blend(0.5, 0.5, 0.5);
+        let poi = c.poi.as_ref().map(|p| p.kind);
+
+        // sorted, so the same zone always gets the same sample
+        let mut samples = c.samples.iter().filter(|(_, s)| s.fits(biome, poi)).collect::<Vec<_>>();
+        samples.sort_by_key(|(name, _)| *name);
+
+        if samples.iter().any(|(_, s)| !s.pois.is_empty()) {
+            samples.retain(|(_, s)| !s.pois.is_empty());
+        }
+
+        if samples.is_empty() {
+            info!("no sample fits zone {}", ctx.idx);
+            ScatterBiome.apply(ctx);
+            return;
+        }
+
+        let (name, sample) = samples[ctx.rand.pick_idx(&samples)];
+        let rules = WfcRules::learn(sample);
+        let wave = rules.edge_wave(c);
+
+        for _ in 0..WFC_TRIES {
+            if let Some(terrain) = rules.collapse(&wave, &mut ctx.rand) {
+                ctx.terrain = terrain;
+                return;
+            }
+        }
+
+        warn!("sample {} would not collapse in zone {}", name, ctx.idx);
+        ScatterBiome.apply(ctx);
+    }
+}
+
+// Collapsing can wall off pockets of open ground. They are tunneled into
+// the largest one, so the stairs and the roads in can always be reached.
+pub struct ConnectOpenGround;
+
+impl BuildStep for ConnectOpenGround {
+    fn name(&self) -> &'static str {
+        "open ground"
+    }
+
+    fn apply(&self, ctx: &mut BuildContext) {
+        let biomes = ctx.constraints.biomes;
+        let solid = ctx.terrain.map(|_, _, t| t.is_solid());
+        let mut tunneled = solid.clone();
+
+        connect_caverns(&mut tunneled, ctx.constraints.structure.as_ref());
+
+        for x in 0..zone_size().0 {
+            for y in 0..zone_size().1 {
+                if *solid.get(x, y).unwrap() && !tunneled.get(x, y).unwrap() {
+                    let u = x as f32 / (zone_size_f32().0 - 1.);
+                    let v = y as f32 / (zone_size_f32().1 - 1.);
+
+                    ctx.terrain.set(x, y, biomes.blend(u, v, 0.5).ground()[0].0);
+                }
+            }
+        }
+    }
+}
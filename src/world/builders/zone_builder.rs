@@ -2,7 +2,7 @@ use std::fmt::Display;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{common::Grid, rendering::{hex, Palette}, world::Terrain};
+use crate::{common::Grid, projection::zone_size, rendering::{hex, Palette}, world::{Biome, Climate, Corners, EdgeFeatureKind, EdgeTile, Faction, Poi, PrefabLibrary, SampleLibrary, Stair, Structure, Terrain, WorldSeed}};
 
 pub const ENABLE_ZONE_SNAPSHOTS: bool = false;
 
@@ -56,15 +56,53 @@ impl TileSnapColor {
             Terrain::Dirt => Self::Orange,
             Terrain::River => Self::Blue,
             Terrain::Footpath => Self::Yellow,
+            Terrain::Sand => Self::Yellow,
+            Terrain::Gravel => Self::Gray(160),
+            Terrain::Clay => Self::Red,
+            Terrain::Mud => Self::Orange,
+            Terrain::Tree => Self::Green,
+            Terrain::Cactus => Self::Green,
+            Terrain::Shrub => Self::Green,
+            Terrain::Boulder => Self::Gray(80),
+            Terrain::Reeds => Self::Green,
+            Terrain::DeadTree => Self::Orange,
+            Terrain::Rail => Self::Red,
+            Terrain::Platform => Self::Orange,
+            Terrain::Bridge => Self::Orange,
+            Terrain::Ford => Self::Blue,
+            Terrain::Banner => Self::Red,
+            Terrain::Fence => Self::Orange,
+            Terrain::Campfire => Self::Red,
+            Terrain::StairsDown => Self::Black,
+            Terrain::StairsUp => Self::White,
+            Terrain::LadderDown => Self::Black,
+            Terrain::LadderUp => Self::White,
+            Terrain::MineShaft => Self::Black,
+            Terrain::CaveFloor => Self::Gray(160),
+            Terrain::CaveWall => Self::Gray(60),
+            Terrain::Wall => Self::Black,
+            Terrain::Floor => Self::Orange,
+            Terrain::Door => Self::Red,
+            Terrain::Counter => Self::Black,
+            Terrain::DeepWater => Self::Blue,
+            Terrain::ShallowWater => Self::Blue,
+            Terrain::Creek => Self::Blue,
+            Terrain::Wash => Self::Yellow,
+            Terrain::Wreck => Self::Gray(100),
+            Terrain::Window => Self::Blue,
         }
     }
 
-    pub fn for_edge(e: u8) -> Self {
-        match e {
-            0 => Self::Gray(127),
-            1 => Self::Blue,   // river
-            2 => Self::Yellow, // footpath
-            _ => Self::White,
+    pub fn for_edge(e: &EdgeTile) -> Self {
+        if e.passable == Some(false) {
+            return Self::Black;
+        }
+
+        match e.top().map(|f| f.kind) {
+            None => Self::Gray(127),
+            Some(EdgeFeatureKind::River) => Self::Blue,
+            Some(EdgeFeatureKind::Footpath) => Self::Yellow,
+            Some(EdgeFeatureKind::Railroad) => Self::Red,
         }
     }
 
@@ -84,15 +122,48 @@ impl TileSnapColor {
 
 #[derive(Clone)]
 pub struct ZoneSnapshot {
+    pub name: &'static str,
     pub data: Grid<TileSnapColor>,
 }
 
 pub struct ZoneConstraints {
     pub idx: usize,
-    pub south: Vec<u8>,
-    pub west: Vec<u8>,
-    pub east: Vec<u8>,
-    pub north: Vec<u8>,
+    pub seed: WorldSeed,
+    pub biomes: Corners<Biome>,
+    pub climate: Climate,
+    pub factions: Corners<Faction>,
+    // the faction whose capital is in this zone
+    pub capital: Option<Faction>,
+    pub is_lake: bool,
+    pub poi: Option<Poi>,
+    pub stair_down: Option<Stair>,
+    pub stair_up: Option<Stair>,
+    pub structure: Option<Structure>,
+    pub prefabs: PrefabLibrary,
+    pub samples: SampleLibrary,
+    pub south: Vec<EdgeTile>,
+    pub west: Vec<EdgeTile>,
+    pub east: Vec<EdgeTile>,
+    pub north: Vec<EdgeTile>,
+}
+
+impl ZoneConstraints {
+    pub fn has_station(&self) -> bool {
+        self.poi.as_ref().is_some_and(|p| p.kind.has_station())
+    }
+
+    // every tile along the four edges of the zone, with its position
+    pub fn edge_tiles(&self) -> impl Iterator<Item = ((usize, usize), &EdgeTile)> {
+        let (w, h) = zone_size();
+
+        self.south
+            .iter()
+            .enumerate()
+            .map(|(x, t)| ((x, 0), t))
+            .chain(self.north.iter().enumerate().map(move |(x, t)| ((x, h - 1), t)))
+            .chain(self.west.iter().enumerate().map(|(y, t)| ((0, y), t)))
+            .chain(self.east.iter().enumerate().map(move |(y, t)| ((w - 1, y), t)))
+    }
 }
 
 pub trait ZoneBuilder {
@@ -0,0 +1,113 @@
+use std::sync::RwLock;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// smallest zone the builders can fit stairs and structures into
+const MIN_ZONE_SIZE: (usize, usize) = (20, 14);
+
+static CONFIG: RwLock<Option<WorldConfig>> = RwLock::new(None);
+
+// Dimensions of the world. Chosen before anything is generated, and stored
+// with the save so a world always loads at the size it was made with.
+// Systems read it as a resource. The sizes are also read everywhere through
+// `map_size()` and `zone_size()`, including by the zone builders which run
+// outside of any system, so the resource is mirrored into a global whenever
+// it changes. Changing it only makes sense before a new world is generated.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WorldConfig {
+    // width, height, depth, in zones
+    pub map_size: (usize, usize, usize),
+    // width and height of a zone, in tiles
+    pub zone_size: (usize, usize),
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        Self {
+            map_size: (8, 6, 4),
+            zone_size: (40, 20),
+        }
+    }
+}
+
+impl WorldConfig {
+    pub fn new(map_size: (usize, usize, usize), zone_size: (usize, usize)) -> Self {
+        let config = Self {
+            map_size: (map_size.0.max(2), map_size.1.max(2), map_size.2.max(1)),
+            zone_size: (
+                zone_size.0.max(MIN_ZONE_SIZE.0),
+                zone_size.1.max(MIN_ZONE_SIZE.1),
+            ),
+        };
+
+        if config.map_size != map_size || config.zone_size != zone_size {
+            warn!("world size clamped to {:?} {:?}", config.map_size, config.zone_size);
+        }
+
+        config
+    }
+
+    // `--map-size <w>x<h>x<d>` and `--zone-size <w>x<h>` on the command
+    // line, or the `MAP_SIZE` and `ZONE_SIZE` env vars. Anything not given
+    // falls back to `base`.
+    pub fn from_override(base: WorldConfig) -> Option<Self> {
+        let map_size = read_override("--map-size", "MAP_SIZE").and_then(|v| match v[..] {
+            [w, h, d] => Some((w, h, d)),
+            _ => None,
+        });
+        let zone_size = read_override("--zone-size", "ZONE_SIZE").and_then(|v| match v[..] {
+            [w, h] => Some((w, h)),
+            _ => None,
+        });
+
+        if map_size.is_none() && zone_size.is_none() {
+            return None;
+        }
+
+        Some(Self::new(
+            map_size.unwrap_or(base.map_size),
+            zone_size.unwrap_or(base.zone_size),
+        ))
+    }
+
+    // make this the config `map_size()` and `zone_size()` read from,
+    // replacing any installed before
+    pub fn install(self) -> Self {
+        *CONFIG.write().unwrap() = Some(self);
+
+        self
+    }
+
+    // the installed config, or the default one if none was
+    #[inline]
+    pub fn get() -> WorldConfig {
+        CONFIG.read().unwrap().unwrap_or_default()
+    }
+}
+
+// keep the global in step with the resource
+pub fn install_world_config(config: Res<WorldConfig>) {
+    config.install();
+}
+
+fn read_override(arg: &str, env: &str) -> Option<Vec<usize>> {
+    let value = std::env::args()
+        .skip_while(|a| a != arg)
+        .nth(1)
+        .or_else(|| std::env::var(env).ok())?;
+
+    let parts = value
+        .trim()
+        .split('x')
+        .map(|v| v.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>();
+
+    match parts {
+        Ok(v) => Some(v),
+        Err(_) => {
+            warn!("invalid world size override '{}'", value);
+            None
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::{
+    camera::Layer,
+    rendering::{Palette, Position, Text},
+};
+
+use super::{Map, Zones};
+
+// zones the player has been to
+#[derive(Resource, Default)]
+pub struct Discoveries {
+    pub zones: HashSet<usize>,
+}
+
+#[derive(Component)]
+pub struct DiscoveryBanner;
+
+// the first time the player enters a named zone, announce it with the
+// discovery banner
+pub fn on_discover_zone(
+    mut cmds: Commands,
+    zones: Res<Zones>,
+    map: Res<Map>,
+    mut discoveries: ResMut<Discoveries>,
+    q_banners: Query<Entity, With<DiscoveryBanner>>,
+) {
+    if !zones.is_changed() || !discoveries.zones.insert(zones.player) {
+        return;
+    }
+
+    let Some(name) = map.get_zone_name(zones.player) else {
+        return;
+    };
+
+    for banner in q_banners.iter() {
+        cmds.entity(banner).despawn_recursive();
+    }
+
+    let subtitle = match map.get_poi(zones.player) {
+        Some(poi) => format!(" You discovered a {}... ", poi.kind.label().to_lowercase()),
+        None => " You discovered... ".to_string(),
+    };
+
+    cmds.spawn((
+        DiscoveryBanner,
+        Text::new(&subtitle).bg(Palette::Black).fg1(Palette::White),
+        Position::f32(17.0, 15., 0.0, Layer::Ui),
+    ));
+
+    let title = format!("♦ {{C|{}}} ♦", name.to_uppercase());
+
+    cmds.spawn((
+        DiscoveryBanner,
+        Text::title(&title).bg(Palette::Black).fg1(Palette::Yellow).fg2(Palette::Cyan),
+        Position::f32(16.0, 14., 0.0, Layer::Ui),
+    ));
+}
@@ -2,29 +2,37 @@ use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    common::{Grid, Grid3d, Rand}, player::Player, projection::{zone_idx, zone_xyz, MAP_SIZE, ZONE_SIZE}, rendering::{Palette, Position, Tile}, GameState
+    common::{Grid, Grid3d}, player::Player, projection::{map_size, zone_idx, zone_size, zone_size_f32, zone_xyz}, rendering::{Palette, Position, Tile}, save::{save_world, try_load_world}, GameState
 };
 
 use super::{
-    ENABLE_ZONE_SNAPSHOTS, LoadZoneEvent, SetZoneStatusEvent, SpawnZoneEvent, UnloadZoneEvent,
-    ZoneConstraints, ZoneData, on_load_zone, on_player_move, on_set_zone_status, on_spawn_zone,
-    on_unload_zone,
+    cycle_zone_builder, edge_mask, generate_factions, generate_hydrology, generate_names, generate_railroads,
+    generate_roads, generate_stairs, generate_structures, install_world_config, on_discover_zone, on_load_zone,
+    on_player_move, on_set_zone_status, on_spawn_zone, on_unload_zone, place_pois, Biome, Climate, Corners, Discoveries,
+    EdgeFeature, EdgeFeatureKind, EdgeTile, Faction, FactionCapital, LoadZoneEvent, Poi, PrefabLibrary, SampleLibrary,
+    SetZoneStatusEvent, SpawnZoneEvent, Stair, Structure, UnloadZoneEvent, WorldConfig, WorldSeed, ZoneBuilders,
+    ZoneConstraints, ZoneData, ZoneEdges, ENABLE_ZONE_SNAPSHOTS,
 };
 
 pub struct MapPlugin;
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut bevy::app::App) {
-        app.init_resource::<Map>()
-            .init_resource::<Zones>()
+        app.init_resource::<Zones>()
+            .init_resource::<Discoveries>()
+            .insert_resource(ZoneBuilders::default().with_override())
             .add_event::<LoadZoneEvent>()
             .add_event::<UnloadZoneEvent>()
             .add_event::<SetZoneStatusEvent>()
             .add_event::<SpawnZoneEvent>()
+            .add_systems(PreUpdate, install_world_config.run_if(resource_changed::<WorldConfig>))
+            .add_systems(OnEnter(GameState::Loading), setup_world)
             .add_systems(
                 Update,
                 (
                     on_player_move,
+                    cycle_zone_builder,
+                    on_discover_zone,
                     load_nearby_zones,
                     on_load_zone,
                     on_unload_zone,
@@ -40,72 +48,270 @@ impl Plugin for MapPlugin {
 
 #[derive(Clone, Resource)]
 pub struct Map {
+    seed: WorldSeed,
     zones: Grid3d<OverworldZone>,
+    // features crossing zone edges, (map_size().0 + 1) x (map_size().1 + 1) x map_size().2
+    edges: Grid3d<ZoneEdges>,
+    // biome on every zone corner, (map_size().0 + 1) x (map_size().1 + 1)
+    biomes: Grid<Biome>,
+    climate: Climate,
+    // owning faction of every zone corner, same size as `biomes`
+    factions: Grid<Faction>,
+    capitals: Vec<FactionCapital>,
 }
 
-impl Default for Map {
-    fn default() -> Self {
-        let zones = Grid3d::init(MAP_SIZE.0, MAP_SIZE.1, MAP_SIZE.2, OverworldZone);
-        Self { zones }
-    }
+// bumped whenever the shape of the saved overworld changes, so an old
+// save is regenerated rather than read back wrong
+pub const OVERWORLD_VERSION: u32 = 2;
+
+#[derive(Deserialize, Serialize, Clone)]
+pub struct WorldData {
+    pub seed: WorldSeed,
+    #[serde(default)]
+    pub config: WorldConfig,
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub overworld: Option<OverworldData>,
+}
+
+// Everything generated for the overworld, saved with the world so it can be
+// reloaded exactly, even after worldgen has changed. The climate is not
+// saved, it is rebuilt from the zone heights.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct OverworldData {
+    pub zones: Grid3d<OverworldZone>,
+    pub edges: Grid3d<ZoneEdges>,
+    pub biomes: Grid<Biome>,
+    pub factions: Grid<Faction>,
+    pub capitals: Vec<FactionCapital>,
+}
+
+// pick the world seed, and generate the overworld from it, or load it from
+// the save when it belongs to the same seed.
+// a cli/env override wins over a saved world, which wins over a new one.
+fn setup_world(mut cmds: Commands, config: Res<WorldConfig>) {
+    let saved = try_load_world();
+    let seed = WorldSeed::from_override()
+        .or_else(|| saved.as_ref().map(|w| w.seed))
+        .unwrap_or_else(WorldSeed::random);
+
+    info!("world seed {}", seed.0);
+
+    let overworld = saved
+        .filter(|w| w.seed == seed && w.config == *config)
+        .filter(|w| {
+            if w.version != OVERWORLD_VERSION {
+                warn!("Overworld save is version {}, regenerating", w.version);
+            }
+
+            w.version == OVERWORLD_VERSION
+        })
+        .and_then(|w| w.overworld);
+
+    let map = match overworld {
+        Some(data) => Map::from_data(seed, data),
+        None => Map::new(seed),
+    };
+
+    save_world(&map.to_save(*config));
+
+    cmds.insert_resource(seed);
+    cmds.insert_resource(map);
 }
 
 pub struct ZoneContinuity {
-    pub south: Vec<u8>,
-    pub west: Vec<u8>,
+    pub south: Vec<EdgeTile>,
+    pub west: Vec<EdgeTile>,
 }
 
 impl Map {
+    pub fn new(seed: WorldSeed) -> Self {
+        let mut zones = Grid3d::init(map_size().0, map_size().1, map_size().2, OverworldZone::default());
+        let mut edges = Grid3d::init(map_size().0 + 1, map_size().1 + 1, map_size().2, ZoneEdges::default());
+
+        generate_hydrology(seed, &mut zones, &mut edges);
+
+        let climate = Climate::new(seed, &zones);
+        let biomes = Self::generate_biomes(&climate);
+        let (capitals, factions) = generate_factions(seed);
+
+        place_pois(seed, &mut zones);
+        generate_roads(seed, &zones, &mut edges);
+        generate_railroads(seed, &zones, &mut edges);
+        generate_structures(seed, &mut zones);
+        generate_stairs(seed, &mut zones);
+        generate_names(seed, &mut zones, &climate);
+
+        Self {
+            seed,
+            zones,
+            edges,
+            biomes,
+            climate,
+            factions,
+            capitals,
+        }
+    }
+
+    pub fn from_data(seed: WorldSeed, data: OverworldData) -> Self {
+        let climate = Climate::new(seed, &data.zones);
+
+        Self {
+            seed,
+            zones: data.zones,
+            edges: data.edges,
+            biomes: data.biomes,
+            climate,
+            factions: data.factions,
+            capitals: data.capitals,
+        }
+    }
+
+    pub fn to_save(&self, config: WorldConfig) -> WorldData {
+        WorldData {
+            seed: self.seed,
+            config,
+            version: OVERWORLD_VERSION,
+            overworld: Some(OverworldData {
+                zones: self.zones.clone(),
+                edges: self.edges.clone(),
+                biomes: self.biomes.clone(),
+                factions: self.factions.clone(),
+                capitals: self.capitals.clone(),
+            }),
+        }
+    }
+
+    // sample the climate on the world tile at every zone corner
+    fn generate_biomes(climate: &Climate) -> Grid<Biome> {
+        Grid::init_fill(map_size().0 + 1, map_size().1 + 1, |x, y| {
+            let wx = (x * zone_size().0) as f32;
+            let wy = (y * zone_size().1) as f32;
+
+            climate.biome(wx, wy)
+        })
+    }
+
+    // biome of each corner of the zone at (x, y)
+    pub fn get_corner_biomes(&self, x: usize, y: usize) -> Corners<Biome> {
+        Corners {
+            sw: *self.biomes.get(x, y).unwrap(),
+            se: *self.biomes.get(x + 1, y).unwrap(),
+            nw: *self.biomes.get(x, y + 1).unwrap(),
+            ne: *self.biomes.get(x + 1, y + 1).unwrap(),
+        }
+    }
+
     fn get_continuity(&self, x: usize, y: usize, z: usize) -> ZoneContinuity {
-        if self.zones.is_oob(x, y, z) {
+        if self.edges.is_oob(x, y, z) {
             return ZoneContinuity {
                 south: vec![],
                 west: vec![],
             };
         }
 
-        let idx = zone_idx(x, y, z);
-        let mut rand = Rand::seed(idx as u64);
+        let edges = self.edges.get(x, y, z).unwrap();
 
-        let mut south = [0; ZONE_SIZE.0];
-        let mut west = [0; ZONE_SIZE.1];
+        let mut south = vec![EdgeTile::default(); zone_size().0];
+        let mut west = vec![EdgeTile::default(); zone_size().1];
 
-        if y > 0 {
-            // river
-            if x % 3 == 0 {
-                let r = rand.range_n(1, ZONE_SIZE.0 as i32 - 1) as usize;
-                south[r] = 1;
-            }
+        let crossings = [
+            (EdgeFeatureKind::River, edges.river),
+            (EdgeFeatureKind::Footpath, edges.footpath),
+            (EdgeFeatureKind::Railroad, edges.railroad),
+        ];
 
-            // path
-            if x % 4 == 0 {
-                let r = rand.range_n(1, ZONE_SIZE.0 as i32 - 1) as usize;
-                south[r] = 2;
+        for (kind, crossing) in crossings {
+            if let Some(r) = crossing.south {
+                south[r].features.push(EdgeFeature { kind, width: crossing.width(true) });
             }
-        }
 
-        if x > 0 {
-            // river
-            if y % 2 == 0 {
-                let r = rand.range_n(1, ZONE_SIZE.1 as i32 - 1) as usize;
-                west[r] = 1;
+            if let Some(r) = crossing.west {
+                west[r].features.push(EdgeFeature { kind, width: crossing.width(false) });
             }
+        }
 
-            // footpaths
-            if y % 2 == 0 {
-                let r = rand.range_n(1, ZONE_SIZE.1 as i32 - 1) as usize;
-                west[r] = 2;
+        // the surface borders come from the seed, so zones match the ones
+        // next door in any order they are built. Caves are walled in.
+        if z == 0 {
+            let corner = |bx: usize, by: usize| self.biomes.get(bx, by).copied().unwrap_or_default();
+            let masks = [
+                (&mut south, edge_mask(self.seed, x, y, true, corner(x, y), corner(x + 1, y))),
+                (&mut west, edge_mask(self.seed, x, y, false, corner(x, y), corner(x, y + 1))),
+            ];
+
+            for (tiles, mask) in masks {
+                // anything crossing the edge keeps its whole width open
+                let crossed = (0..tiles.len())
+                    .map(|i| {
+                        tiles
+                            .iter()
+                            .enumerate()
+                            .any(|(j, t)| t.features.iter().any(|f| i.abs_diff(j) <= f.width / 2))
+                    })
+                    .collect::<Vec<_>>();
+
+                for (i, open) in mask.into_iter().enumerate() {
+                    tiles[i].passable = Some(open || crossed[i]);
+                }
             }
         }
 
-        ZoneContinuity {
-            south: south.to_vec(),
-            west: west.to_vec(),
+        ZoneContinuity { south, west }
+    }
+
+    // owning faction of each corner of the zone at (x, y)
+    pub fn get_corner_factions(&self, x: usize, y: usize) -> Corners<Faction> {
+        Corners {
+            sw: *self.factions.get(x, y).unwrap(),
+            se: *self.factions.get(x + 1, y).unwrap(),
+            nw: *self.factions.get(x, y + 1).unwrap(),
+            ne: *self.factions.get(x + 1, y + 1).unwrap(),
         }
     }
 
-    pub fn get_zone_constraints(&self, idx: usize) -> ZoneConstraints {
+    pub fn get_poi(&self, idx: usize) -> Option<&Poi> {
+        let (x, y, z) = zone_xyz(idx);
+
+        self.zones.get(x, y, z)?.poi.as_ref()
+    }
+
+    pub fn get_zone_name(&self, idx: usize) -> Option<&str> {
+        let (x, y, z) = zone_xyz(idx);
+
+        self.zones.get(x, y, z)?.name.as_deref()
+    }
+
+    pub fn get_zone(&self, x: usize, y: usize, z: usize) -> Option<&OverworldZone> {
+        self.zones.get(x, y, z)
+    }
+
+    // features crossing the southern and western edges of the zone at (x, y, z)
+    pub fn get_edges(&self, x: usize, y: usize, z: usize) -> Option<&ZoneEdges> {
+        self.edges.get(x, y, z)
+    }
+
+    // biome in the middle of the zone at (x, y)
+    pub fn get_biome(&self, x: usize, y: usize) -> Biome {
+        let wx = (x as f32 + 0.5) * zone_size_f32().0;
+        let wy = (y as f32 + 0.5) * zone_size_f32().1;
+
+        self.climate.biome(wx, wy)
+    }
+
+    pub fn get_capital(&self, x: usize, y: usize) -> Option<FactionCapital> {
+        self.capitals.iter().find(|c| c.x == x && c.y == y).copied()
+    }
+
+    pub fn get_zone_constraints(
+        &self,
+        idx: usize,
+        prefabs: &PrefabLibrary,
+        samples: &SampleLibrary,
+    ) -> ZoneConstraints {
         let (x, y, z) = zone_xyz(idx);
+        let zone = self.zones.get(x, y, z).unwrap();
         let own = self.get_continuity(x, y, z);
 
         let east = self.get_continuity(x + 1, y, z);
@@ -113,6 +319,21 @@ impl Map {
 
         ZoneConstraints {
             idx,
+            seed: self.seed,
+            biomes: self.get_corner_biomes(x, y),
+            climate: self.climate.clone(),
+            factions: self.get_corner_factions(x, y),
+            capital: match z {
+                0 => self.get_capital(x, y).map(|c| c.faction),
+                _ => None,
+            },
+            is_lake: zone.is_lake,
+            poi: zone.poi.clone(),
+            stair_down: zone.stair_down,
+            stair_up: zone.stair_up,
+            structure: zone.structure,
+            prefabs: prefabs.clone(),
+            samples: samples.clone(),
             north: north.south,
             west: own.west,
             south: own.south,
@@ -161,10 +382,34 @@ impl Zone {
     pub fn idx(&self) -> usize {
         self.idx
     }
+
+    // terrain at a local zone position
+    #[inline]
+    pub fn get_terrain(&self, x: usize, y: usize) -> Option<&Terrain> {
+        self.terrain.get(x, y)
+    }
 }
 
-#[derive(Clone, Default)]
-pub struct OverworldZone;
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct OverworldZone {
+    // surface height, only set on z = 0
+    pub height: f32,
+    // a river ends in this zone
+    pub is_lake: bool,
+    // the river running through this zone, the first one to reach it
+    pub river: Option<usize>,
+    // what the zone is known by, from its point of interest, river
+    // or mountain range
+    pub name: Option<String>,
+    // every point of interest is reachable by road,
+    // and every town has a railroad station
+    pub poi: Option<Poi>,
+    // the way down to (x, y, z + 1), and up to (x, y, z - 1)
+    pub stair_down: Option<Stair>,
+    pub stair_up: Option<Stair>,
+    // a building reaching down through this zone
+    pub structure: Option<Structure>,
+}
 
 #[repr(u8)]
 #[derive(Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq)]
@@ -174,6 +419,40 @@ pub enum Terrain {
     Dirt = 2,
     River = 3,
     Footpath = 4,
+    Sand = 5,
+    Gravel = 6,
+    Clay = 7,
+    Mud = 8,
+    Tree = 9,
+    Cactus = 10,
+    Shrub = 11,
+    Boulder = 12,
+    Reeds = 13,
+    DeadTree = 14,
+    Rail = 15,
+    Platform = 16,
+    Bridge = 17,
+    Ford = 18,
+    Banner = 19,
+    Fence = 20,
+    Campfire = 21,
+    StairsDown = 22,
+    StairsUp = 23,
+    LadderDown = 24,
+    LadderUp = 25,
+    MineShaft = 26,
+    CaveFloor = 27,
+    CaveWall = 28,
+    Wall = 29,
+    Floor = 30,
+    Door = 31,
+    Counter = 32,
+    DeepWater = 33,
+    ShallowWater = 34,
+    Creek = 35,
+    Wash = 36,
+    Wreck = 37,
+    Window = 38,
 }
 
 impl Terrain {
@@ -183,6 +462,40 @@ impl Terrain {
             Terrain::Dirt => '.',
             Terrain::River => '~',
             Terrain::Footpath => '░',
+            Terrain::Sand => '.',
+            Terrain::Gravel => ':',
+            Terrain::Clay => '.',
+            Terrain::Mud => ',',
+            Terrain::Tree => '♣',
+            Terrain::Cactus => '¥',
+            Terrain::Shrub => '"',
+            Terrain::Boulder => '•',
+            Terrain::Reeds => '⌠',
+            Terrain::DeadTree => '┬',
+            Terrain::Rail => '╫',
+            Terrain::Platform => '=',
+            Terrain::Bridge => '═',
+            Terrain::Ford => '≈',
+            Terrain::Banner => '¶',
+            Terrain::Fence => '#',
+            Terrain::Campfire => '☼',
+            Terrain::StairsDown => '>',
+            Terrain::StairsUp => '<',
+            Terrain::LadderDown => '>',
+            Terrain::LadderUp => '<',
+            Terrain::MineShaft => 'O',
+            Terrain::CaveFloor => '.',
+            Terrain::CaveWall => '#',
+            Terrain::Wall => '#',
+            Terrain::Floor => '.',
+            Terrain::Door => '+',
+            Terrain::Counter => '=',
+            Terrain::DeepWater => '~',
+            Terrain::ShallowWater => '~',
+            Terrain::Creek => '~',
+            Terrain::Wash => '.',
+            Terrain::Wreck => '%',
+            Terrain::Window => '"',
         }
     }
 
@@ -192,6 +505,40 @@ impl Terrain {
             Terrain::Dirt => Tile::Dirt,
             Terrain::River => Tile::Water,
             Terrain::Footpath => Tile::Dirt,
+            Terrain::Sand => Tile::Sand,
+            Terrain::Gravel => Tile::Gravel,
+            Terrain::Clay => Tile::Dirt,
+            Terrain::Mud => Tile::Dirt,
+            Terrain::Tree => Tile::Tree,
+            Terrain::Cactus => Tile::Cactus,
+            Terrain::Shrub => Tile::Shrub,
+            Terrain::Boulder => Tile::Boulder,
+            Terrain::Reeds => Tile::Reeds,
+            Terrain::DeadTree => Tile::DeadTree,
+            Terrain::Rail => Tile::Rail,
+            Terrain::Platform => Tile::Planks,
+            Terrain::Bridge => Tile::Planks,
+            Terrain::Ford => Tile::Gravel,
+            Terrain::Banner => Tile::Sign,
+            Terrain::Fence => Tile::Fence,
+            Terrain::Campfire => Tile::Campfire,
+            Terrain::StairsDown => Tile::Stairs,
+            Terrain::StairsUp => Tile::Stairs,
+            Terrain::LadderDown => Tile::LadderHole,
+            Terrain::LadderUp => Tile::Ladder,
+            Terrain::MineShaft => Tile::LadderHole,
+            Terrain::CaveFloor => Tile::Gravel,
+            Terrain::CaveWall => Tile::Blank,
+            Terrain::Wall => Tile::Blank,
+            Terrain::Floor => Tile::Planks,
+            Terrain::Door => Tile::Planks,
+            Terrain::Counter => Tile::Planks,
+            Terrain::DeepWater => Tile::Water,
+            Terrain::ShallowWater => Tile::Water,
+            Terrain::Creek => Tile::Water,
+            Terrain::Wash => Tile::Gravel,
+            Terrain::Wreck => Tile::Planks,
+            Terrain::Window => Tile::Blank,
         }
     }
 
@@ -201,6 +548,125 @@ impl Terrain {
             Terrain::Dirt => (None, Some(Palette::Brown.into())),
             Terrain::River => (Some(Palette::Blue.into()), Some(Palette::Cyan.into())),
             Terrain::Footpath => (None, Some(Palette::Brown.into())),
+            Terrain::Sand => (None, Some(Palette::Yellow.into())),
+            Terrain::Gravel => (None, Some(Palette::Gray.into())),
+            Terrain::Clay => (None, Some(Palette::Red.into())),
+            Terrain::Mud => (None, Some(Palette::Brown.into())),
+            Terrain::Tree => (None, Some(Palette::Green.into())),
+            Terrain::Cactus => (None, Some(Palette::LightGreen.into())),
+            Terrain::Shrub => (None, Some(Palette::Green.into())),
+            Terrain::Boulder => (None, Some(Palette::Gray.into())),
+            Terrain::Reeds => (None, Some(Palette::LightGreen.into())),
+            Terrain::DeadTree => (None, Some(Palette::Brown.into())),
+            Terrain::Rail => (None, Some(Palette::Gray.into())),
+            Terrain::Platform => (None, Some(Palette::Brown.into())),
+            Terrain::Bridge => (Some(Palette::Blue.into()), Some(Palette::Orange.into())),
+            Terrain::Ford => (Some(Palette::LightBlue.into()), Some(Palette::Brown.into())),
+            Terrain::Banner => (None, Some(Palette::Red.into())),
+            Terrain::Fence => (None, Some(Palette::Brown.into())),
+            Terrain::Campfire => (None, Some(Palette::Orange.into())),
+            Terrain::StairsDown => (None, Some(Palette::White.into())),
+            Terrain::StairsUp => (None, Some(Palette::White.into())),
+            Terrain::LadderDown => (Some(Palette::Black.into()), Some(Palette::Brown.into())),
+            Terrain::LadderUp => (None, Some(Palette::Brown.into())),
+            Terrain::MineShaft => (Some(Palette::Black.into()), Some(Palette::Gray.into())),
+            Terrain::CaveFloor => (None, Some(Palette::Gray.into())),
+            Terrain::CaveWall => (Some(Palette::Gray.into()), None),
+            Terrain::Wall => (Some(Palette::Brown.into()), None),
+            Terrain::Floor => (None, Some(Palette::Brown.into())),
+            Terrain::Door => (Some(Palette::Black.into()), Some(Palette::Orange.into())),
+            Terrain::Counter => (Some(Palette::Brown.into()), Some(Palette::Orange.into())),
+            Terrain::DeepWater => (Some(Palette::Black.into()), Some(Palette::Blue.into())),
+            Terrain::ShallowWater => (Some(Palette::Blue.into()), Some(Palette::LightBlue.into())),
+            Terrain::Creek => (None, Some(Palette::LightBlue.into())),
+            Terrain::Wash => (None, Some(Palette::Yellow.into())),
+            Terrain::Wreck => (None, Some(Palette::Gray.into())),
+            Terrain::Window => (Some(Palette::LightBlue.into()), None),
+        }
+    }
+
+    // can't be walked through
+    pub fn is_solid(&self) -> bool {
+        matches!(
+            self,
+            Terrain::Tree
+                | Terrain::Cactus
+                | Terrain::Boulder
+                | Terrain::DeadTree
+                | Terrain::Fence
+                | Terrain::Wall
+                | Terrain::CaveWall
+                | Terrain::Counter
+                | Terrain::Wreck
+                | Terrain::Window
+        )
+    }
+
+    // part of a building, left alone by roads and markers
+    pub fn is_building(&self) -> bool {
+        matches!(
+            self,
+            Terrain::Wall | Terrain::Window | Terrain::Floor | Terrain::Door | Terrain::Counter
+        )
+    }
+
+    // the player can climb down to the zone below from here
+    pub fn is_way_down(&self) -> bool {
+        matches!(self, Terrain::StairsDown | Terrain::LadderDown | Terrain::MineShaft)
+    }
+
+    // the player can climb up to the zone above from here
+    pub fn is_way_up(&self) -> bool {
+        matches!(self, Terrain::StairsUp | Terrain::LadderUp)
+    }
+
+    // water flows through this tile. bridges and fords keep the river
+    // underneath them continuous. a wash is a dry creek bed, so it isn't.
+    pub fn is_water(&self) -> bool {
+        matches!(
+            self,
+            Terrain::River
+                | Terrain::DeepWater
+                | Terrain::ShallowWater
+                | Terrain::Creek
+                | Terrain::Bridge
+                | Terrain::Ford
+        )
+    }
+
+    // too deep to wade, the player has to swim
+    pub fn is_deep(&self) -> bool {
+        matches!(self, Terrain::River | Terrain::DeepWater)
+    }
+
+    // how much slower than open ground it is to walk or wade onto this
+    // tile. None where it is too deep to wade, and the player swims instead.
+    pub fn move_cost(&self) -> Option<f32> {
+        match self {
+            t if t.is_deep() => None,
+            Terrain::ShallowWater | Terrain::Ford => Some(2.),
+            Terrain::Creek | Terrain::Mud => Some(1.5),
+            _ => Some(1.),
+        }
+    }
+
+    // how deep the water is, used so overlapping rivers only ever deepen
+    fn water_depth(&self) -> u8 {
+        match self {
+            Terrain::Wash => 1,
+            Terrain::Creek => 2,
+            Terrain::ShallowWater => 3,
+            Terrain::River => 4,
+            Terrain::DeepWater => 5,
+            _ => 0,
+        }
+    }
+
+    // the deeper of two kinds of water
+    pub fn deeper(self, other: Terrain) -> Terrain {
+        match other.water_depth() > self.water_depth() {
+            true => other,
+            false => self,
         }
     }
 }
@@ -258,11 +724,11 @@ fn load_nearby_zones(
     for idx in zones.active.iter() {
         let (x, y, z) = zone_xyz(*idx);
 
-        if y < MAP_SIZE.1 - 1 {
+        if y < map_size().1 - 1 {
             let north_idx = zone_idx(x, y + 1, z);
             needed_zones.push(north_idx);
 
-            if x < MAP_SIZE.0 - 1 {
+            if x < map_size().0 - 1 {
                 let north_east_idx = zone_idx(x + 1, y + 1, z);
                 needed_zones.push(north_east_idx);
             }
@@ -277,7 +743,7 @@ fn load_nearby_zones(
             let south_idx = zone_idx(x, y - 1, z);
             needed_zones.push(south_idx);
 
-            if x < MAP_SIZE.0 - 1 {
+            if x < map_size().0 - 1 {
                 let south_east_idx = zone_idx(x + 1, y - 1, z);
                 needed_zones.push(south_east_idx);
             }
@@ -293,7 +759,7 @@ fn load_nearby_zones(
             needed_zones.push(above_idx);
         }
 
-        if x < MAP_SIZE.0 - 1 {
+        if x < map_size().0 - 1 {
             let east_idx = zone_idx(x + 1, y, z);
             needed_zones.push(east_idx);
         }
@@ -303,7 +769,7 @@ fn load_nearby_zones(
             needed_zones.push(west_idx);
         }
 
-        if z < MAP_SIZE.2 - 1 {
+        if z < map_size().2 - 1 {
             let below_idx = zone_idx(x, y, z + 1);
             needed_zones.push(below_idx);
         }
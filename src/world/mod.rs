@@ -1,9 +1,23 @@
 mod builders;
+mod config;
+mod discovery;
 mod map;
+mod overworld;
+mod prefabs;
+mod samples;
+mod seed;
 mod snapshot;
+mod world_map;
 mod zone_gen;
 
 pub use builders::*;
+pub use config::*;
+pub use discovery::*;
 pub use map::*;
+pub use overworld::*;
+pub use prefabs::*;
+pub use samples::*;
+pub use seed::*;
 pub use snapshot::*;
+pub use world_map::*;
 pub use zone_gen::*;
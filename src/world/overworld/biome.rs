@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+use crate::world::Terrain;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum Biome {
+    Desert = 1,
+    #[default]
+    Prairie = 2,
+    Mountain = 3,
+    Forest = 4,
+    Badlands = 5,
+    Swamp = 6,
+}
+
+impl Biome {
+    // weighted ground terrain for this biome
+    pub fn ground(&self) -> &'static [(Terrain, f32)] {
+        match self {
+            Biome::Desert => &[(Terrain::Sand, 0.9), (Terrain::Dirt, 0.1)],
+            Biome::Prairie => &[(Terrain::Grass, 0.8), (Terrain::Dirt, 0.2)],
+            Biome::Mountain => &[(Terrain::Gravel, 0.6), (Terrain::Dirt, 0.4)],
+            Biome::Forest => &[(Terrain::Grass, 0.9), (Terrain::Dirt, 0.1)],
+            Biome::Badlands => &[(Terrain::Clay, 0.7), (Terrain::Sand, 0.3)],
+            Biome::Swamp => &[(Terrain::Mud, 0.6), (Terrain::Grass, 0.4)],
+        }
+    }
+
+    // features scattered over the ground, with the chance of each per tile
+    pub fn features(&self) -> &'static [(Terrain, f32)] {
+        match self {
+            Biome::Desert => &[(Terrain::Cactus, 0.02), (Terrain::Boulder, 0.01)],
+            Biome::Prairie => &[(Terrain::Shrub, 0.03)],
+            Biome::Mountain => &[(Terrain::Boulder, 0.15), (Terrain::Shrub, 0.02)],
+            Biome::Forest => &[(Terrain::Tree, 0.3), (Terrain::Shrub, 0.05)],
+            Biome::Badlands => &[(Terrain::Boulder, 0.05), (Terrain::DeadTree, 0.01)],
+            Biome::Swamp => &[(Terrain::Reeds, 0.15), (Terrain::Tree, 0.05)],
+        }
+    }
+
+    // what stands in the way where the zone next door is impassable
+    pub fn blocker(&self) -> Terrain {
+        match self {
+            Biome::Desert => Terrain::Cactus,
+            Biome::Prairie => Terrain::Fence,
+            Biome::Mountain => Terrain::Boulder,
+            Biome::Forest => Terrain::Tree,
+            Biome::Badlands => Terrain::Boulder,
+            Biome::Swamp => Terrain::Tree,
+        }
+    }
+
+    // pick the terrain for a single tile, given two random values in [0, 1)
+    pub fn pick_terrain(&self, r_feature: f32, r_ground: f32) -> Terrain {
+        let mut acc = 0.;
+
+        for (terrain, chance) in self.features() {
+            acc += chance;
+
+            if r_feature < acc {
+                return *terrain;
+            }
+        }
+
+        let ground = self.ground();
+        let mut acc = 0.;
+
+        for (terrain, weight) in ground {
+            acc += weight;
+
+            if r_ground < acc {
+                return *terrain;
+            }
+        }
+
+        ground[0].0
+    }
+}
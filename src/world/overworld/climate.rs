@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use crate::{
+    common::{Grid, Grid3d, Perlin},
+    projection::{map_size, zone_size, zone_size_f32},
+    world::{OverworldZone, WorldSeed},
+};
+
+use super::Biome;
+
+// biomes by temperature (rows, cold to hot) and moisture (columns, dry to wet)
+const WHITTAKER: [[Biome; 3]; 3] = [
+    [Biome::Mountain, Biome::Forest, Biome::Forest],
+    [Biome::Prairie, Biome::Prairie, Biome::Forest],
+    [Biome::Desert, Biome::Badlands, Biome::Swamp],
+];
+
+// anything this high up is mountains, whatever the climate
+const MOUNTAIN_ELEVATION: f32 = 0.72;
+// even the driest ground has a little plant life, and builders divide by it
+const MIN_VEGETATION: f32 = 0.05;
+
+#[derive(Clone, Copy)]
+pub struct ClimateSample {
+    pub temperature: f32,
+    pub moisture: f32,
+    pub elevation: f32,
+}
+
+// World-space temperature and moisture fields. Sampled in world tile
+// coordinates, so they are continuous across zones.
+#[derive(Clone)]
+pub struct Climate {
+    // built once and shared, they are sampled for every tile of every zone
+    temperature: Arc<Perlin>,
+    moisture: Arc<Perlin>,
+    // surface height of every zone, interpolated between zone centers
+    heights: Grid<f32>,
+}
+
+impl Climate {
+    pub fn new(seed: WorldSeed, zones: &Grid3d<OverworldZone>) -> Self {
+        let heights = Grid::init_fill(map_size().0, map_size().1, |x, y| {
+            zones.get(x, y, 0).unwrap().height
+        });
+
+        Self {
+            temperature: Arc::new(Perlin::new(seed.mix(6) as u32, 0.01, 2, 2.)),
+            moisture: Arc::new(Perlin::new(seed.mix(7) as u32, 0.012, 3, 2.)),
+            heights,
+        }
+    }
+
+    pub fn elevation(&self, wx: f32, wy: f32) -> f32 {
+        // zone heights sit in the middle of each zone
+        let zx = (wx / zone_size_f32().0 - 0.5).clamp(0., (map_size().0 - 1) as f32);
+        let zy = (wy / zone_size_f32().1 - 0.5).clamp(0., (map_size().1 - 1) as f32);
+        let (x0, y0) = (zx.floor() as usize, zy.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(map_size().0 - 1), (y0 + 1).min(map_size().1 - 1));
+        let (u, v) = (zx.fract(), zy.fract());
+
+        let h = |x, y| *self.heights.get(x, y).unwrap();
+        let south = h(x0, y0) * (1. - u) + h(x1, y0) * u;
+        let north = h(x0, y1) * (1. - u) + h(x1, y1) * u;
+
+        south * (1. - v) + north * v
+    }
+
+    // hot in the south and cold in the north, cooler the higher up
+    pub fn temperature(&self, wx: f32, wy: f32) -> f32 {
+        let latitude = wy / (map_size().1 * zone_size().1) as f32;
+        let elevation = self.elevation(wx, wy);
+
+        let t = (1. - latitude) * 0.6 + self.temperature.get(wx, wy) * 0.4 - elevation * 0.3;
+
+        t.clamp(0., 1.)
+    }
+
+    // wetter in low lying ground
+    pub fn moisture(&self, wx: f32, wy: f32) -> f32 {
+        let elevation = self.elevation(wx, wy);
+
+        let m = self.moisture.get(wx, wy) * 0.8 + (1. - elevation) * 0.2;
+
+        m.clamp(0., 1.)
+    }
+
+    pub fn sample(&self, wx: f32, wy: f32) -> ClimateSample {
+        ClimateSample {
+            temperature: self.temperature(wx, wy),
+            moisture: self.moisture(wx, wy),
+            elevation: self.elevation(wx, wy),
+        }
+    }
+
+    // how much plant life there is, as a multiplier around 1
+    pub fn vegetation(&self, wx: f32, wy: f32) -> f32 {
+        let s = self.sample(wx, wy);
+
+        (0.25 + s.moisture * 1.5 - (s.temperature - 0.5).abs() * 0.5).max(MIN_VEGETATION)
+    }
+
+    // how much surface water there is, in [0, 1]
+    pub fn water(&self, wx: f32, wy: f32) -> f32 {
+        let s = self.sample(wx, wy);
+
+        (s.moisture - s.temperature * 0.3 + 0.15).clamp(0., 1.)
+    }
+
+    pub fn biome(&self, wx: f32, wy: f32) -> Biome {
+        Biome::from_climate(self.sample(wx, wy))
+    }
+}
+
+impl Biome {
+    // pick a biome from a Whittaker-style temperature / moisture table
+    pub fn from_climate(sample: ClimateSample) -> Self {
+        if sample.elevation > MOUNTAIN_ELEVATION {
+            return Biome::Mountain;
+        }
+
+        let band = |v: f32| ((v * 3.) as usize).min(2);
+
+        WHITTAKER[band(sample.temperature)][band(sample.moisture)]
+    }
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+
+// A value tracked on each of the four corners of a zone. Zones own
+// their south-western corner, the other three come from neighbours.
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Corners<T> {
+    pub sw: T,
+    pub se: T,
+    pub nw: T,
+    pub ne: T,
+}
+
+impl<T: Copy + PartialEq> Corners<T> {
+    pub fn is_uniform(&self) -> bool {
+        self.sw == self.se && self.sw == self.nw && self.sw == self.ne
+    }
+
+    // bilinear weight of each corner at (u, v), both in [0, 1]
+    pub fn weights(&self, u: f32, v: f32) -> [(T, f32); 4] {
+        [
+            (self.sw, (1. - u) * (1. - v)),
+            (self.se, u * (1. - v)),
+            (self.nw, (1. - u) * v),
+            (self.ne, u * v),
+        ]
+    }
+
+    // the value of the corner closest to (u, v)
+    pub fn nearest(&self, u: f32, v: f32) -> T {
+        self.weights(u, v)
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(value, _)| value)
+            .unwrap_or(self.sw)
+    }
+
+    // pick a corner value at (u, v), where `r` is a random value in [0, 1).
+    // corners closer to (u, v) are more likely to be picked, which dithers
+    // the transition between differing corners.
+    pub fn blend(&self, u: f32, v: f32, r: f32) -> T {
+        if self.is_uniform() {
+            return self.sw;
+        }
+
+        let mut acc = 0.;
+
+        for (value, weight) in self.weights(u, v) {
+            acc += weight;
+
+            if r < acc {
+                return value;
+            }
+        }
+
+        self.ne
+    }
+}
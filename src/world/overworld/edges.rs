@@ -0,0 +1,185 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::Rand,
+    projection::{map_size, zone_size},
+    world::WorldSeed,
+};
+
+use super::Biome;
+
+const MASK_SALT: u64 = 4;
+
+// Position of a feature crossing a zone's southern and western edges.
+// The northern and eastern edges belong to the neighbouring zones.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EdgeCrossing {
+    pub south: Option<usize>,
+    pub west: Option<usize>,
+    // in tiles, centered on the crossing. zero is treated as one
+    pub south_width: usize,
+    pub west_width: usize,
+}
+
+impl EdgeCrossing {
+    #[inline]
+    pub fn get(&self, is_south: bool) -> Option<usize> {
+        match is_south {
+            true => self.south,
+            false => self.west,
+        }
+    }
+
+    #[inline]
+    pub fn set(&mut self, is_south: bool, pos: usize) {
+        match is_south {
+            true => self.south = Some(pos),
+            false => self.west = Some(pos),
+        }
+    }
+
+    #[inline]
+    pub fn width(&self, is_south: bool) -> usize {
+        match is_south {
+            true => self.south_width.max(1),
+            false => self.west_width.max(1),
+        }
+    }
+
+    #[inline]
+    pub fn set_width(&mut self, is_south: bool, width: usize) {
+        match is_south {
+            true => self.south_width = width,
+            false => self.west_width = width,
+        }
+    }
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum EdgeFeatureKind {
+    River = 1,
+    Footpath = 2,
+    Railroad = 3,
+}
+
+// A single feature crossing a tile of a zone edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EdgeFeature {
+    pub kind: EdgeFeatureKind,
+    // in tiles, centered on this one
+    pub width: usize,
+}
+
+// Everything crossing one tile of a zone edge, bottom-most first, so a
+// footpath listed after a river crosses it on a bridge.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct EdgeTile {
+    pub features: Vec<EdgeFeature>,
+    // whether the tile can be walked across, the same for the zones on both
+    // sides of the edge. `None` leaves it up to the builder.
+    pub passable: Option<bool>,
+}
+
+impl EdgeTile {
+    // the feature on top, which is the one that can be seen
+    pub fn top(&self) -> Option<&EdgeFeature> {
+        self.features.last()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum EdgeSide {
+    North,
+    South,
+    East,
+    West,
+}
+
+// Everything that crosses the southern and western edges of a zone.
+// Stored one larger than the map, so the north and east edges of the
+// last row and column of zones have somewhere to live.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct ZoneEdges {
+    pub river: EdgeCrossing,
+    pub footpath: EdgeCrossing,
+    pub railroad: EdgeCrossing,
+}
+
+impl ZoneEdges {
+    // every position already used on the southern (or western) edge
+    pub fn taken(&self, is_south: bool) -> Vec<usize> {
+        [
+            self.river.get(is_south),
+            self.footpath.get(is_south),
+            self.railroad.get(is_south),
+        ]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+}
+
+// Deterministic position along the southern (or western) edge of the zone
+// at (x, y), for a feature identified by `salt`. Stays clear of the corners
+// and of any position in `taken`.
+pub fn edge_position(
+    seed: WorldSeed,
+    x: usize,
+    y: usize,
+    is_south: bool,
+    salt: u64,
+    taken: &[usize],
+) -> usize {
+    let mut rand = Rand::seed(seed.mix((salt << 32) | edge_key(x, y, is_south)));
+    let len = match is_south {
+        true => zone_size().0,
+        false => zone_size().1,
+    };
+
+    loop {
+        let pos = rand.range_n(2, len as i32 - 2) as usize;
+
+        if !taken.contains(&pos) {
+            return pos;
+        }
+    }
+}
+
+// Which tiles along the southern (or western) edge of the zone at (x, y) can
+// be walked across. Worked out from the seed alone, so the two zones sharing
+// the edge agree on it whichever of them is built first. The edge is about
+// as overgrown as the biomes at either end of it.
+pub fn edge_mask(seed: WorldSeed, x: usize, y: usize, is_south: bool, from: Biome, to: Biome) -> Vec<bool> {
+    let mut rand = Rand::seed(seed.mix((MASK_SALT << 32) | edge_key(x, y, is_south)));
+    let len = match is_south {
+        true => zone_size().0,
+        false => zone_size().1,
+    };
+
+    let blocked = |biome: Biome| {
+        biome
+            .features()
+            .iter()
+            .filter(|(t, _)| t.is_solid())
+            .map(|(_, chance)| chance)
+            .sum::<f32>()
+    };
+
+    (0..len)
+        .map(|i| {
+            let u = i as f32 / (len - 1) as f32;
+            let biome = match rand.random() < u {
+                true => to,
+                false => from,
+            };
+
+            !rand.bool(blocked(biome))
+        })
+        .collect()
+}
+
+// identifies the southern (or western) edge of the zone at (x, y)
+fn edge_key(x: usize, y: usize, is_south: bool) -> u64 {
+    ((x * (map_size().1 + 1) + y) * 2 + is_south as usize) as u64
+}
@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Grid, Perlin, Rand},
+    projection::map_size,
+    world::{Terrain, WorldSeed},
+};
+
+#[repr(u8)]
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Faction {
+    #[default]
+    Homesteaders = 1,
+    RailroadCompany = 2,
+    Outlaws = 3,
+    Cavalry = 4,
+}
+
+impl Faction {
+    pub const ALL: [Faction; 4] = [
+        Faction::Homesteaders,
+        Faction::RailroadCompany,
+        Faction::Outlaws,
+        Faction::Cavalry,
+    ];
+
+    // how far the faction's territory reaches, relative to the others
+    pub fn strength(&self) -> f32 {
+        match self {
+            Faction::Homesteaders => 1.2,
+            Faction::RailroadCompany => 1.0,
+            Faction::Outlaws => 0.8,
+            Faction::Cavalry => 1.0,
+        }
+    }
+
+    // what the faction puts up where roads enter its territory
+    pub fn marker(&self) -> Terrain {
+        match self {
+            Faction::Homesteaders => Terrain::Fence,
+            Faction::RailroadCompany => Terrain::Banner,
+            Faction::Outlaws => Terrain::Campfire,
+            Faction::Cavalry => Terrain::Banner,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize)]
+pub struct FactionCapital {
+    pub faction: Faction,
+    pub x: usize,
+    pub y: usize,
+}
+
+// Seed a capital for every faction, spread apart, and give each zone corner
+// to the capital closest to it. Distances are scaled by faction strength and
+// warped with noise, so the borders between territories wander.
+pub fn generate_factions(seed: WorldSeed) -> (Vec<FactionCapital>, Grid<Faction>) {
+    let mut rand = Rand::seed(seed.mix(8));
+    let mut capitals: Vec<FactionCapital> = vec![];
+
+    for faction in Faction::ALL {
+        let mut best: Option<(FactionCapital, f32)> = None;
+
+        // best of a few candidates, furthest from the other capitals
+        for _ in 0..8 {
+            let x = rand.range_n(0, map_size().0 as i32) as usize;
+            let y = rand.range_n(0, map_size().1 as i32) as usize;
+
+            let spread = capitals
+                .iter()
+                .map(|c| (c.x as f32 - x as f32).hypot(c.y as f32 - y as f32))
+                .fold(f32::MAX, f32::min);
+
+            if best.is_none_or(|(_, s)| spread > s) {
+                best = Some((FactionCapital { faction, x, y }, spread));
+            }
+        }
+
+        capitals.push(best.unwrap().0);
+    }
+
+    let nz = Perlin::new(seed.mix(9) as u32, 0.3, 2, 2.);
+
+    let territory = Grid::init_fill(map_size().0 + 1, map_size().1 + 1, |x, y| {
+        // corners sit between zones, capitals in the middle of one
+        let (cx, cy) = (x as f32 - 0.5, y as f32 - 0.5);
+        let warp = nz.get(x as f32, y as f32) * 2. - 1.;
+
+        capitals
+            .iter()
+            .map(|c| {
+                let d = (c.x as f32 - cx).hypot(c.y as f32 - cy) + warp;
+                (c.faction, d / c.faction.strength())
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(f, _)| f)
+            .unwrap_or_default()
+    });
+
+    (capitals, territory)
+}
@@ -0,0 +1,219 @@
+use crate::{
+    common::{Grid3d, Perlin, Rand},
+    projection::map_size,
+    world::{OverworldZone, WorldSeed},
+};
+
+use super::{edge_position, EdgeSide, ZoneEdges};
+
+// minimum height a zone needs to be a candidate river source
+const RIVER_SOURCE_HEIGHT: f32 = 0.6;
+const RIVER_SALT: u64 = 1;
+
+// width of a river, from the number of river zones draining into it
+fn river_width(flow: usize) -> usize {
+    match flow {
+        0..=2 => 1,
+        3..=5 => 2,
+        _ => 4,
+    }
+}
+
+enum Flow {
+    // flows into a neighbouring zone
+    Zone(usize, usize),
+    // flows over the edge of the map
+    Exit(EdgeSide),
+    // lowest point around, the river pools into a lake
+    Pit,
+}
+
+// Build the surface heightmap, and trace rivers from high sources down
+// to lakes or the edge of the map. Every step a river takes is strictly
+// downhill, and is recorded as a crossing on the shared zone edge. Rivers
+// widen as tributaries join them, from creeks up to major rivers.
+pub fn generate_hydrology(
+    seed: WorldSeed,
+    zones: &mut Grid3d<OverworldZone>,
+    edges: &mut Grid3d<ZoneEdges>,
+) {
+    let nz = Perlin::new(seed.mix(3) as u32, 0.2, 3, 2.);
+
+    for x in 0..map_size().0 {
+        for y in 0..map_size().1 {
+            // lower the land towards the map edges, so water can drain off
+            let dx = x.min(map_size().0 - 1 - x) as f32 / (map_size().0 as f32 / 2.);
+            let dy = y.min(map_size().1 - 1 - y) as f32 / (map_size().1 as f32 / 2.);
+            let falloff = dx.min(dy).clamp(0., 1.);
+            let height = nz.get(x as f32, y as f32) * 0.7 + falloff * 0.3;
+
+            zones.get_mut(x, y, 0).unwrap().height = height;
+        }
+    }
+
+    let mut rand = Rand::seed(seed.mix(4));
+    let mut has_river = vec![false; map_size().0 * map_size().1];
+    // river zones draining through each zone, including itself
+    let mut flow = vec![0; map_size().0 * map_size().1];
+    let mut downstream: Vec<Option<(usize, usize)>> = vec![None; map_size().0 * map_size().1];
+    // every crossing made, and the zone the water leaves through it
+    let mut crossings = vec![];
+    let mut sources = vec![];
+
+    for x in 0..map_size().0 {
+        for y in 0..map_size().1 {
+            let height = zones.get(x, y, 0).unwrap().height;
+
+            if height > RIVER_SOURCE_HEIGHT {
+                sources.push((x, y, height));
+            }
+        }
+    }
+
+    // highest sources first, so later rivers join earlier ones as tributaries
+    sources.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+    let max_rivers = (map_size().0 * map_size().1 / 12).max(2);
+    let mut river_count = 0;
+
+    for (sx, sy, _) in sources {
+        if river_count >= max_rivers {
+            break;
+        }
+
+        if has_river[sx * map_size().1 + sy] || !rand.bool(0.6) {
+            continue;
+        }
+
+        let river = river_count;
+        river_count += 1;
+
+        let (mut x, mut y) = (sx, sy);
+        let mut drained = 0;
+
+        loop {
+            let i = x * map_size().1 + y;
+
+            drained += 1;
+            has_river[i] = true;
+            flow[i] += drained;
+            zones.get_mut(x, y, 0).unwrap().river = Some(river);
+
+            match get_flow(zones, x, y) {
+                Flow::Zone(nx, ny) => {
+                    crossings.push(set_river_crossing(seed, edges, x, y, nx, ny));
+                    downstream[i] = Some((nx, ny));
+
+                    // joined an existing river, it already knows the way down,
+                    // and carries this river's water from here on
+                    if has_river[nx * map_size().1 + ny] {
+                        let mut next = Some((nx, ny));
+
+                        while let Some((jx, jy)) = next {
+                            flow[jx * map_size().1 + jy] += drained;
+                            next = downstream[jx * map_size().1 + jy];
+                        }
+
+                        break;
+                    }
+
+                    (x, y) = (nx, ny);
+                }
+                Flow::Exit(side) => {
+                    let (ex, ey) = match side {
+                        EdgeSide::North => (x, y + 1),
+                        EdgeSide::East => (x + 1, y),
+                        _ => (x, y),
+                    };
+                    let is_south = matches!(side, EdgeSide::North | EdgeSide::South);
+                    set_crossing(seed, edges, ex, ey, is_south);
+                    crossings.push((ex, ey, is_south, i));
+                    break;
+                }
+                Flow::Pit => {
+                    zones.get_mut(x, y, 0).unwrap().is_lake = true;
+                    break;
+                }
+            }
+        }
+    }
+
+    for (x, y, is_south, from) in crossings {
+        let edge = edges.get_mut(x, y, 0).unwrap();
+        edge.river.set_width(is_south, river_width(flow[from]));
+    }
+}
+
+fn get_flow(zones: &Grid3d<OverworldZone>, x: usize, y: usize) -> Flow {
+    let height = zones.get(x, y, 0).unwrap().height;
+    let mut lowest: Option<(usize, usize, f32)> = None;
+    let mut neighbors = vec![];
+
+    if x > 0 {
+        neighbors.push((x - 1, y));
+    }
+
+    if x < map_size().0 - 1 {
+        neighbors.push((x + 1, y));
+    }
+
+    if y > 0 {
+        neighbors.push((x, y - 1));
+    }
+
+    if y < map_size().1 - 1 {
+        neighbors.push((x, y + 1));
+    }
+
+    for (nx, ny) in neighbors {
+        let h = zones.get(nx, ny, 0).unwrap().height;
+
+        if h < height && lowest.is_none_or(|(_, _, l)| h < l) {
+            lowest = Some((nx, ny, h));
+        }
+    }
+
+    if let Some((nx, ny, _)) = lowest {
+        return Flow::Zone(nx, ny);
+    }
+
+    if y == 0 {
+        Flow::Exit(EdgeSide::South)
+    } else if x == 0 {
+        Flow::Exit(EdgeSide::West)
+    } else if y == map_size().1 - 1 {
+        Flow::Exit(EdgeSide::North)
+    } else if x == map_size().0 - 1 {
+        Flow::Exit(EdgeSide::East)
+    } else {
+        Flow::Pit
+    }
+}
+
+// record a river crossing on the edge shared by two neighbouring zones,
+// returning where it was stored along with the zone the water leaves
+fn set_river_crossing(
+    seed: WorldSeed,
+    edges: &mut Grid3d<ZoneEdges>,
+    x: usize,
+    y: usize,
+    nx: usize,
+    ny: usize,
+) -> (usize, usize, bool, usize) {
+    let (ex, ey, is_south) = match nx == x {
+        true => (x, y.max(ny), true),
+        false => (x.max(nx), y, false),
+    };
+
+    set_crossing(seed, edges, ex, ey, is_south);
+
+    (ex, ey, is_south, x * map_size().1 + y)
+}
+
+// record a crossing on the southern (or western) edge of the zone at (x, y)
+fn set_crossing(seed: WorldSeed, edges: &mut Grid3d<ZoneEdges>, x: usize, y: usize, is_south: bool) {
+    let edge = edges.get_mut(x, y, 0).unwrap();
+    let pos = edge_position(seed, x, y, is_south, RIVER_SALT, &edge.taken(is_south));
+
+    edge.river.set(is_south, pos);
+}
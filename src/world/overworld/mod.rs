@@ -0,0 +1,25 @@
+mod biome;
+mod climate;
+mod corners;
+mod edges;
+mod factions;
+mod hydrology;
+mod names;
+mod poi;
+mod railroads;
+mod roads;
+mod stairs;
+mod structures;
+
+pub use biome::*;
+pub use climate::*;
+pub use corners::*;
+pub use edges::*;
+pub use factions::*;
+pub use hydrology::*;
+pub use names::*;
+pub use poi::*;
+pub use railroads::*;
+pub use roads::*;
+pub use stairs::*;
+pub use structures::*;
@@ -0,0 +1,219 @@
+use std::collections::HashSet;
+
+use crate::{
+    common::{Grid3d, Rand},
+    projection::{map_size, zone_size_f32},
+    world::{Biome, Climate, OverworldZone, PoiKind, WorldSeed},
+};
+
+const ADJECTIVES: [&str; 16] = [
+    "Dry", "Red", "Dead Man's", "Rattlesnake", "Coyote", "Buzzard", "Silver", "Lone",
+    "Broken", "Dusty", "Copper", "Whiskey", "Crooked", "Black", "Sweetwater", "Hangman's",
+];
+
+const LANDMARKS: [&str; 14] = [
+    "Gulch", "Bend", "Springs", "Flats", "Hollow", "Ridge", "Wells", "Crossing",
+    "Junction", "Gap", "Butte", "Mesa", "Canyon", "Draw",
+];
+
+// surnames are built from a start and an end, "Cal" + "loway"
+const SURNAME_STARTS: [&str; 14] = [
+    "Cal", "Mor", "Hast", "Wick", "Bran", "Dun", "Har", "Mac", "Gar", "Tal", "Ben", "Sut",
+    "Ash", "Pem",
+];
+
+const SURNAME_ENDS: [&str; 12] = [
+    "loway", "gan", "ings", "ham", "ford", "ley", "ton", "ett", "son", "ner", "by", "wick",
+];
+
+const SPANISH: [&str; 10] = [
+    "Blanco", "Colorado", "Verde", "Seco", "Bravo", "Lobo", "Madre", "Diablo", "Oro", "Pecos",
+];
+
+// Seeded names for places. Every name handed out is unique within a world.
+pub struct NameGenerator {
+    rand: Rand,
+    used: HashSet<String>,
+}
+
+impl NameGenerator {
+    pub fn new(seed: WorldSeed) -> Self {
+        Self {
+            rand: Rand::seed(seed.mix(12)),
+            used: HashSet::new(),
+        }
+    }
+
+    pub fn poi(&mut self, kind: PoiKind) -> String {
+        self.unique(|g| match kind {
+            PoiKind::Capital | PoiKind::Town => match g.rand.range_n(0, 3) {
+                0 => format!("{} {}", g.adjective(), g.landmark()),
+                1 => format!("{}ville", g.surname()),
+                _ => format!("{} {}", g.surname(), g.landmark()),
+            },
+            PoiKind::Fort => format!("Fort {}", g.surname()),
+            PoiKind::GoldMine => format!("{} Lode", g.adjective()),
+            PoiKind::Mine => format!("{} Mine", g.surname()),
+            PoiKind::Ranch => match g.rand.bool(0.5) {
+                true => format!("{} Ranch", g.surname()),
+                false => format!("Bar {} Ranch", (b'A' + g.rand.range_n(0, 26) as u8) as char),
+            },
+            PoiKind::Camp => format!("{}'s Camp", g.surname()),
+            PoiKind::Ruin => format!("Old {}", g.surname()),
+            PoiKind::Cemetery => format!("{} Boot Hill", g.adjective()),
+        })
+    }
+
+    pub fn river(&mut self) -> String {
+        self.unique(|g| match g.rand.range_n(0, 3) {
+            0 => format!("{} River", g.adjective()),
+            1 => format!("{} River", g.surname()),
+            _ => format!("Rio {}", g.spanish()),
+        })
+    }
+
+    pub fn range(&mut self) -> String {
+        self.unique(|g| match g.rand.range_n(0, 3) {
+            0 => format!("{} Mountains", g.adjective()),
+            1 => format!("{} Range", g.surname()),
+            _ => format!("Sierra {}", g.spanish()),
+        })
+    }
+
+    // keep trying until an unused name comes up, falling back to numbering
+    // it if the tables run dry
+    fn unique<F>(&mut self, mut make: F) -> String
+    where
+        F: FnMut(&mut Self) -> String,
+    {
+        let mut name = make(self);
+
+        for _ in 0..20 {
+            if !self.used.contains(&name) {
+                break;
+            }
+
+            name = make(self);
+        }
+
+        let base = name.clone();
+        let mut n = 2;
+
+        while self.used.contains(&name) {
+            name = format!("{} {}", base, n);
+            n += 1;
+        }
+
+        self.used.insert(name.clone());
+        name
+    }
+
+    fn adjective(&mut self) -> &'static str {
+        self.rand.pick(&ADJECTIVES)
+    }
+
+    fn landmark(&mut self) -> &'static str {
+        self.rand.pick(&LANDMARKS)
+    }
+
+    fn spanish(&mut self) -> &'static str {
+        self.rand.pick(&SPANISH)
+    }
+
+    fn surname(&mut self) -> String {
+        format!("{}{}", self.rand.pick(&SURNAME_STARTS), self.rand.pick(&SURNAME_ENDS))
+    }
+}
+
+// Name every point of interest, river and mountain range. A zone is known
+// by the name of its point of interest, or else the river running through
+// it, or else the mountain range it is part of.
+pub fn generate_names(seed: WorldSeed, zones: &mut Grid3d<OverworldZone>, climate: &Climate) {
+    let mut names = NameGenerator::new(seed);
+    let (width, height, _) = map_size();
+
+    for x in 0..width {
+        for y in 0..height {
+            let zone = zones.get_mut(x, y, 0).unwrap();
+
+            if let Some(poi) = zone.poi.as_mut() {
+                poi.name = names.poi(poi.kind);
+                zone.name = Some(poi.name.clone());
+            }
+        }
+    }
+
+    let mut rivers: Vec<String> = vec![];
+
+    for x in 0..width {
+        for y in 0..height {
+            let zone = zones.get_mut(x, y, 0).unwrap();
+
+            let Some(river) = zone.river else {
+                continue;
+            };
+
+            while rivers.len() <= river {
+                rivers.push(names.river());
+            }
+
+            if zone.name.is_none() {
+                zone.name = Some(rivers[river].clone());
+            }
+        }
+    }
+
+    // mountain ranges are groups of neighbouring mountain zones
+    let is_mountain = |x: usize, y: usize| {
+        let wx = (x as f32 + 0.5) * zone_size_f32().0;
+        let wy = (y as f32 + 0.5) * zone_size_f32().1;
+
+        climate.biome(wx, wy) == Biome::Mountain
+    };
+    let mut seen = vec![false; width * height];
+
+    for x in 0..width {
+        for y in 0..height {
+            if seen[x * height + y] || !is_mountain(x, y) {
+                continue;
+            }
+
+            let mut range = vec![];
+            let mut open = vec![(x, y)];
+            seen[x * height + y] = true;
+
+            while let Some((cx, cy)) = open.pop() {
+                range.push((cx, cy));
+
+                let neighbors = [
+                    (cx.wrapping_sub(1), cy),
+                    (cx + 1, cy),
+                    (cx, cy.wrapping_sub(1)),
+                    (cx, cy + 1),
+                ];
+
+                for (nx, ny) in neighbors {
+                    if nx < width && ny < height && !seen[nx * height + ny] && is_mountain(nx, ny) {
+                        seen[nx * height + ny] = true;
+                        open.push((nx, ny));
+                    }
+                }
+            }
+
+            // a lone mountain zone isn't much of a range
+            if range.len() < 2 {
+                continue;
+            }
+
+            let name = names.range();
+
+            for (rx, ry) in range {
+                let zone = zones.get_mut(rx, ry, 0).unwrap();
+
+                if zone.name.is_none() {
+                    zone.name = Some(name.clone());
+                }
+            }
+        }
+    }
+}
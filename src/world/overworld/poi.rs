@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Distance, Grid3d, Rand},
+    projection::map_size,
+    world::{OverworldZone, WorldSeed},
+};
+
+// minimum distance between any two points of interest, in zones
+const POI_SPACING: f32 = 2.;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PoiKind {
+    Capital = 1,
+    Town = 2,
+    GoldMine = 3,
+    Mine = 4,
+    Ranch = 5,
+    Fort = 6,
+    Camp = 7,
+    Ruin = 8,
+    Cemetery = 9,
+}
+
+impl PoiKind {
+    // placed in this order, so unique points of interest always find room
+    pub const ALL: [PoiKind; 9] = [
+        PoiKind::Capital,
+        PoiKind::GoldMine,
+        PoiKind::Town,
+        PoiKind::Fort,
+        PoiKind::Mine,
+        PoiKind::Ranch,
+        PoiKind::Camp,
+        PoiKind::Ruin,
+        PoiKind::Cemetery,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PoiKind::Capital => "Capital",
+            PoiKind::Town => "Town",
+            PoiKind::GoldMine => "Gold Mine",
+            PoiKind::Mine => "Mine",
+            PoiKind::Ranch => "Ranch",
+            PoiKind::Fort => "Fort",
+            PoiKind::Camp => "Camp",
+            PoiKind::Ruin => "Ruin",
+            PoiKind::Cemetery => "Cemetery",
+        }
+    }
+
+    // exactly one of these exists in every world
+    pub fn is_unique(&self) -> bool {
+        matches!(self, PoiKind::Capital | PoiKind::GoldMine)
+    }
+
+    // how many to place, per 48 zones of surface
+    fn density(&self) -> f32 {
+        match self {
+            PoiKind::Capital | PoiKind::GoldMine => 0.,
+            PoiKind::Town => 2.,
+            PoiKind::Fort => 1.,
+            PoiKind::Mine => 1.,
+            PoiKind::Ranch => 1.5,
+            PoiKind::Camp => 1.,
+            PoiKind::Ruin => 0.5,
+            PoiKind::Cemetery => 0.5,
+        }
+    }
+
+    // minimum distance to another point of interest of the same kind
+    fn spacing(&self) -> f32 {
+        match self {
+            PoiKind::Town | PoiKind::Fort => 3.,
+            _ => POI_SPACING,
+        }
+    }
+
+    // towns big enough for a railroad station
+    pub fn has_station(&self) -> bool {
+        matches!(self, PoiKind::Capital | PoiKind::Town)
+    }
+
+    // mines are dug into high ground, ranches sit on the flats
+    fn allows_height(&self, height: f32) -> bool {
+        match self {
+            PoiKind::GoldMine | PoiKind::Mine => height > 0.45,
+            PoiKind::Ranch => height < 0.6,
+            _ => true,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Poi {
+    pub kind: PoiKind,
+    // given out once every point of interest is placed, see `generate_names`
+    pub name: String,
+}
+
+// Place typed points of interest over the surface. Unique ones go first and
+// are guaranteed a spot, the rest are scattered according to their density,
+// keeping apart from each other and out of lakes.
+pub fn place_pois(seed: WorldSeed, zones: &mut Grid3d<OverworldZone>) {
+    let mut rand = Rand::seed(seed.mix(5));
+    let mut placed: Vec<([i32; 3], PoiKind)> = vec![];
+    let scale = (map_size().0 * map_size().1) as f32 / 48.;
+
+    for kind in PoiKind::ALL {
+        let target = match kind.is_unique() {
+            true => 1,
+            false => (kind.density() * scale).round() as usize,
+        };
+        let mut count = 0;
+        let mut attempts = 0;
+
+        while count < target && attempts < 200 {
+            attempts += 1;
+
+            let x = rand.range_n(0, map_size().0 as i32) as usize;
+            let y = rand.range_n(0, map_size().1 as i32) as usize;
+
+            if try_place(zones, &mut placed, x, y, kind, true) {
+                count += 1;
+            }
+        }
+
+        // unique points of interest must exist, so fall back to the first
+        // zone that is free, ignoring preferences
+        if kind.is_unique() && count == 0 {
+            'search: for x in 0..map_size().0 {
+                for y in 0..map_size().1 {
+                    if try_place(zones, &mut placed, x, y, kind, false) {
+                        break 'search;
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn try_place(
+    zones: &mut Grid3d<OverworldZone>,
+    placed: &mut Vec<([i32; 3], PoiKind)>,
+    x: usize,
+    y: usize,
+    kind: PoiKind,
+    is_strict: bool,
+) -> bool {
+    let pos = [x as i32, y as i32, 0];
+    let zone = zones.get_mut(x, y, 0).unwrap();
+
+    if zone.poi.is_some() || zone.is_lake {
+        return false;
+    }
+
+    if is_strict {
+        if !kind.allows_height(zone.height) {
+            return false;
+        }
+
+        let too_close = placed.iter().any(|(p, k)| {
+            let d = Distance::chebyshev(*p, pos);
+            d < POI_SPACING || (*k == kind && d < kind.spacing())
+        });
+
+        if too_close {
+            return false;
+        }
+    }
+
+    zone.poi = Some(Poi {
+        kind,
+        name: String::new(),
+    });
+    placed.push((pos, kind));
+
+    true
+}
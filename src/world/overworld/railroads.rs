@@ -0,0 +1,119 @@
+use crate::{
+    common::{astar, AStarSettings, Distance, Grid3d},
+    projection::map_size,
+    world::{OverworldZone, WorldSeed},
+};
+
+use super::{edge_position, shared_edge, spanning_tree, ZoneEdges};
+
+const RAILROAD_SALT: u64 = 3;
+
+// extra cost for a 90 degree turn, railroads would rather run straight
+const TURN_COST: f32 = 1.5;
+
+// heading, used to penalize turns. `NONE` is used for the first step.
+const NORTH: usize = 0;
+const EAST: usize = 1;
+const SOUTH: usize = 2;
+const WEST: usize = 3;
+const NONE: usize = 4;
+
+// Connect every station with railroad lines. Stations are joined along a
+// minimum spanning tree, and each line is routed zone by zone with A*,
+// which tracks the heading so that turns and slopes are costly.
+pub fn generate_railroads(
+    seed: WorldSeed,
+    zones: &Grid3d<OverworldZone>,
+    edges: &mut Grid3d<ZoneEdges>,
+) {
+    let mut stations = vec![];
+
+    for x in 0..map_size().0 {
+        for y in 0..map_size().1 {
+            let zone = zones.get(x, y, 0).unwrap();
+
+            if zone.poi.as_ref().is_some_and(|p| p.kind.has_station()) {
+                stations.push([x, y]);
+            }
+        }
+    }
+
+    for (a, b) in spanning_tree(&stations) {
+        route_railroad(seed, zones, edges, a, b);
+    }
+}
+
+fn route_railroad(
+    seed: WorldSeed,
+    zones: &Grid3d<OverworldZone>,
+    edges: &mut Grid3d<ZoneEdges>,
+    start: [usize; 2],
+    goal: [usize; 2],
+) {
+    let existing = &*edges;
+
+    let result = astar(AStarSettings {
+        start: [start[0], start[1], NONE],
+        is_goal: |[x, y, _]| [x, y] == goal,
+        cost: |[ax, ay, a_heading], [bx, by, b_heading]| {
+            let ha = zones.get(ax, ay, 0).unwrap().height;
+            let hb = zones.get(bx, by, 0).unwrap().height;
+            let (x, y, is_south) = shared_edge([ax, ay], [bx, by]);
+
+            if existing.get(x, y, 0).unwrap().railroad.get(is_south).is_some() {
+                return 0.3;
+            }
+
+            let turn = match a_heading == NONE || a_heading == b_heading {
+                true => 0.,
+                false => TURN_COST,
+            };
+
+            1. + (ha - hb).abs() * 20. + turn
+        },
+        heuristic: |[x, y, _]| {
+            0.3 * Distance::manhattan([x as i32, y as i32, 0], [goal[0] as i32, goal[1] as i32, 0])
+        },
+        neighbors: |[x, y, heading]| {
+            let mut n = vec![];
+
+            // no turning back on itself
+            if x > 0 && heading != EAST {
+                n.push([x - 1, y, WEST]);
+            }
+
+            if y > 0 && heading != NORTH {
+                n.push([x, y - 1, SOUTH]);
+            }
+
+            if !zones.is_oob(x + 1, y, 0) && heading != WEST {
+                n.push([x + 1, y, EAST]);
+            }
+
+            if !zones.is_oob(x, y + 1, 0) && heading != SOUTH {
+                n.push([x, y + 1, NORTH]);
+            }
+
+            n
+        },
+        max_depth: 10000,
+    });
+
+    if !result.is_success {
+        return;
+    }
+
+    for pair in result.path.windows(2) {
+        let [ax, ay, _] = pair[0];
+        let [bx, by, _] = pair[1];
+        let (x, y, is_south) = shared_edge([ax, ay], [bx, by]);
+        let edge = edges.get_mut(x, y, 0).unwrap();
+
+        if edge.railroad.get(is_south).is_some() {
+            continue;
+        }
+
+        let pos = edge_position(seed, x, y, is_south, RAILROAD_SALT, &edge.taken(is_south));
+        edge.railroad.set(is_south, pos);
+    }
+}
@@ -0,0 +1,144 @@
+use crate::{
+    common::{astar, AStarSettings, Distance, Grid3d},
+    projection::map_size,
+    world::{OverworldZone, WorldSeed},
+};
+
+use super::{edge_position, ZoneEdges};
+
+const FOOTPATH_SALT: u64 = 2;
+
+// Connect every point of interest with a footpath. Points are joined along
+// a minimum spanning tree, and each link is routed zone by zone with A*,
+// preferring flat ground and re-using roads that already exist.
+pub fn generate_roads(
+    seed: WorldSeed,
+    zones: &Grid3d<OverworldZone>,
+    edges: &mut Grid3d<ZoneEdges>,
+) {
+    let mut pois = vec![];
+
+    for x in 0..map_size().0 {
+        for y in 0..map_size().1 {
+            if zones.get(x, y, 0).unwrap().poi.is_some() {
+                pois.push([x, y]);
+            }
+        }
+    }
+
+    for (a, b) in spanning_tree(&pois) {
+        route_road(seed, zones, edges, a, b);
+    }
+}
+
+// Prim's algorithm over the straight line distance between points
+pub fn spanning_tree(points: &[[usize; 2]]) -> Vec<([usize; 2], [usize; 2])> {
+    let mut links = vec![];
+
+    if points.is_empty() {
+        return links;
+    }
+
+    let mut connected = vec![points[0]];
+    let mut remaining = points[1..].to_vec();
+
+    while !remaining.is_empty() {
+        let mut best: Option<(usize, [usize; 2], f32)> = None;
+
+        for (r_idx, r) in remaining.iter().enumerate() {
+            for c in connected.iter() {
+                let d = Distance::euclidean([r[0] as i32, r[1] as i32, 0], [c[0] as i32, c[1] as i32, 0]);
+
+                if best.is_none_or(|(_, _, b)| d < b) {
+                    best = Some((r_idx, *c, d));
+                }
+            }
+        }
+
+        let (r_idx, c, _) = best.unwrap();
+        let r = remaining.swap_remove(r_idx);
+
+        links.push((c, r));
+        connected.push(r);
+    }
+
+    links
+}
+
+fn route_road(
+    seed: WorldSeed,
+    zones: &Grid3d<OverworldZone>,
+    edges: &mut Grid3d<ZoneEdges>,
+    start: [usize; 2],
+    goal: [usize; 2],
+) {
+    let existing = &*edges;
+
+    let result = astar(AStarSettings {
+        start,
+        is_goal: |p| p == goal,
+        cost: |a, b| {
+            let ha = zones.get(a[0], a[1], 0).unwrap().height;
+            let hb = zones.get(b[0], b[1], 0).unwrap().height;
+            let (x, y, is_south) = shared_edge(a, b);
+
+            // follow existing roads, so the network merges instead of
+            // running parallel tracks
+            if existing.get(x, y, 0).unwrap().footpath.get(is_south).is_some() {
+                return 0.3;
+            }
+
+            1. + (ha - hb).abs() * 10.
+        },
+        heuristic: |[x, y]| {
+            0.3 * Distance::manhattan([x as i32, y as i32, 0], [goal[0] as i32, goal[1] as i32, 0])
+        },
+        neighbors: |[x, y]| {
+            let mut n = vec![];
+
+            if x > 0 {
+                n.push([x - 1, y]);
+            }
+
+            if y > 0 {
+                n.push([x, y - 1]);
+            }
+
+            if !zones.is_oob(x + 1, y, 0) {
+                n.push([x + 1, y]);
+            }
+
+            if !zones.is_oob(x, y + 1, 0) {
+                n.push([x, y + 1]);
+            }
+
+            n
+        },
+        max_depth: 10000,
+    });
+
+    if !result.is_success {
+        return;
+    }
+
+    for pair in result.path.windows(2) {
+        let (x, y, is_south) = shared_edge(pair[0], pair[1]);
+        let edge = edges.get_mut(x, y, 0).unwrap();
+
+        if edge.footpath.get(is_south).is_some() {
+            continue;
+        }
+
+        let pos = edge_position(seed, x, y, is_south, FOOTPATH_SALT, &edge.taken(is_south));
+        edge.footpath.set(is_south, pos);
+    }
+}
+
+// the zone (x, y) whose southern (or western) edge lies between a and b
+pub fn shared_edge(a: [usize; 2], b: [usize; 2]) -> (usize, usize, bool) {
+    if a[0] == b[0] {
+        (a[0], a[1].max(b[1]), true)
+    } else {
+        (a[0].max(b[0]), a[1], false)
+    }
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Grid3d, Rand},
+    projection::{map_size, zone_size},
+    world::{OverworldZone, PoiKind, Terrain, WorldSeed},
+};
+
+// keep stairs this far from the zone edges
+const STAIR_MARGIN: i32 = 3;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum StairKind {
+    Stairs = 1,
+    Ladder = 2,
+    MineShaft = 3,
+}
+
+impl StairKind {
+    pub fn down(&self) -> Terrain {
+        match self {
+            StairKind::Stairs => Terrain::StairsDown,
+            StairKind::Ladder => Terrain::LadderDown,
+            StairKind::MineShaft => Terrain::MineShaft,
+        }
+    }
+
+    pub fn up(&self) -> Terrain {
+        match self {
+            StairKind::Stairs => Terrain::StairsUp,
+            StairKind::Ladder | StairKind::MineShaft => Terrain::LadderUp,
+        }
+    }
+}
+
+// A connection between two z-levels, at the same local position in the
+// zone above and the zone below.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Stair {
+    pub kind: StairKind,
+    pub x: usize,
+    pub y: usize,
+}
+
+// Give every zone a way down to the zone beneath it. The down stair of
+// (x, y, z) is always at the same position as the up stair of (x, y, z + 1).
+pub fn generate_stairs(seed: WorldSeed, zones: &mut Grid3d<OverworldZone>) {
+    let mut rand = Rand::seed(seed.mix(10));
+
+    for x in 0..map_size().0 {
+        for y in 0..map_size().1 {
+            for z in 0..map_size().2 - 1 {
+                let zone = zones.get(x, y, z).unwrap();
+
+                if zone.stair_down.is_some() {
+                    continue;
+                }
+
+                let kind = match z {
+                    0 => match zone.poi.as_ref().map(|p| p.kind) {
+                        Some(PoiKind::Mine | PoiKind::GoldMine) => StairKind::MineShaft,
+                        _ => StairKind::Ladder,
+                    },
+                    _ => StairKind::Stairs,
+                };
+
+                let up = zone.stair_up;
+
+                let stair = loop {
+                    let sx = rand.range_n(STAIR_MARGIN, zone_size().0 as i32 - STAIR_MARGIN) as usize;
+                    let sy = rand.range_n(STAIR_MARGIN, zone_size().1 as i32 - STAIR_MARGIN) as usize;
+
+                    // don't put the way down on top of the way up
+                    if up.is_none_or(|u| u.x.abs_diff(sx) > 2 || u.y.abs_diff(sy) > 2) {
+                        break Stair { kind, x: sx, y: sy };
+                    }
+                };
+
+                zones.get_mut(x, y, z).unwrap().stair_down = Some(stair);
+                zones.get_mut(x, y, z + 1).unwrap().stair_up = Some(stair);
+            }
+        }
+    }
+}
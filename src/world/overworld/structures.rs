@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    common::{Grid3d, Rand},
+    projection::{map_size, zone_size},
+    world::{OverworldZone, PoiKind, Stair, StairKind, WorldSeed},
+};
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum StructureKind {
+    // a saloon with a cellar
+    Saloon = 1,
+    // a bank with a vault in the basement
+    Bank = 2,
+    // a mine head over shafts that run down several levels
+    MineHead = 3,
+}
+
+impl StructureKind {
+    // footprint size, including walls
+    fn size(&self) -> (usize, usize) {
+        match self {
+            StructureKind::Saloon => (11, 6),
+            StructureKind::Bank => (9, 6),
+            StructureKind::MineHead => (7, 5),
+        }
+    }
+
+    fn stair_kind(&self, level: usize) -> StairKind {
+        match (self, level) {
+            (StructureKind::MineHead, 0) => StairKind::MineShaft,
+            (StructureKind::MineHead, _) => StairKind::Ladder,
+            _ => StairKind::Stairs,
+        }
+    }
+}
+
+// A building that spans several z-levels, from the surface down. Every level
+// gets the same footprint, so walls and stairs line up between them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Structure {
+    pub kind: StructureKind,
+    // south west corner of the footprint, in local zone coordinates
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+    // number of z-levels, starting at the surface
+    pub levels: usize,
+}
+
+impl Structure {
+    // the door is in the middle of the south wall, on every level
+    pub fn door(&self) -> (usize, usize) {
+        (self.x + self.width / 2, self.y)
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.x + self.width && y >= self.y && y < self.y + self.height
+    }
+
+    pub fn is_wall(&self, x: usize, y: usize) -> bool {
+        self.contains(x, y)
+            && (x == self.x
+                || y == self.y
+                || x == self.x + self.width - 1
+                || y == self.y + self.height - 1)
+            && (x, y) != self.door()
+    }
+
+    // stairs alternate between the back corners, so the way up and the way
+    // down on the same level never meet
+    fn stair(&self, level: usize) -> Stair {
+        let x = match level % 2 {
+            0 => self.x + self.width - 2,
+            _ => self.x + 1,
+        };
+
+        Stair {
+            kind: self.kind.stair_kind(level),
+            x,
+            y: self.y + self.height - 2,
+        }
+    }
+}
+
+// Reserve footprints for structures at towns and mines, on every z-level they
+// span, and run the stairs between those levels inside the footprint. This
+// runs before `generate_stairs`, which keeps any stairs placed here.
+pub fn generate_structures(seed: WorldSeed, zones: &mut Grid3d<OverworldZone>) {
+    let mut rand = Rand::seed(seed.mix(11));
+
+    for x in 0..map_size().0 {
+        for y in 0..map_size().1 {
+            let Some(poi) = zones.get(x, y, 0).unwrap().poi.as_ref().map(|p| p.kind) else {
+                continue;
+            };
+
+            let (kind, levels) = match poi {
+                PoiKind::Capital | PoiKind::Town => match rand.bool(0.5) {
+                    true => (StructureKind::Saloon, 2),
+                    false => (StructureKind::Bank, 2),
+                },
+                PoiKind::Mine => (StructureKind::MineHead, 3),
+                PoiKind::GoldMine => (StructureKind::MineHead, map_size().2),
+                _ => continue,
+            };
+
+            // the door opens onto the end of the road, in the middle of the
+            // zone, with the top wall kept off the zone edge in short zones
+            let (width, height) = kind.size();
+            let structure = Structure {
+                kind,
+                x: zone_size().0 / 2 - width / 2,
+                y: (zone_size().1 / 2 + 1).min(zone_size().1 - 1 - height),
+                width,
+                height,
+                levels: levels.min(map_size().2),
+            };
+
+            for z in 0..structure.levels {
+                zones.get_mut(x, y, z).unwrap().structure = Some(structure);
+
+                // the lowest level also has a way further down
+                if z < map_size().2 - 1 {
+                    let stair = structure.stair(z);
+
+                    zones.get_mut(x, y, z).unwrap().stair_down = Some(stair);
+                    zones.get_mut(x, y, z + 1).unwrap().stair_up = Some(stair);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use std::{collections::HashMap, sync::Arc};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext, LoadedFolder},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::{
+    common::{Grid, Rand},
+    projection::zone_size,
+};
+
+use super::{Biome, PoiKind, Terrain};
+
+// how many spots are tried before a prefab is given up on
+const PREFAB_PLACEMENT_TRIES: usize = 40;
+
+pub struct PrefabPlugin;
+
+impl Plugin for PrefabPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Prefab>()
+            .init_asset_loader::<PrefabLoader>()
+            .init_resource::<PrefabLibrary>()
+            .add_systems(Startup, load_prefabs)
+            .add_systems(Update, on_prefab_changed);
+    }
+}
+
+// A hand-drawn room or landmark. Each character of the rows is looked up in
+// the legend, characters missing from it leave the ground underneath alone.
+#[derive(Asset, TypePath, Clone, Deserialize)]
+pub struct Prefab {
+    pub legend: HashMap<char, Terrain>,
+    // top row first, as it reads in the file
+    pub rows: Vec<String>,
+    // chance of appearing in a zone it fits in
+    pub chance: f32,
+    // only placed in these biomes, or any when empty
+    #[serde(default)]
+    pub biomes: Vec<Biome>,
+    // only placed in zones with one of these points of interest, or in
+    // zones without one when empty
+    #[serde(default)]
+    pub pois: Vec<PoiKind>,
+}
+
+// quarter turns clockwise, applied after mirroring
+#[derive(Clone, Copy, Default)]
+pub struct PrefabTransform {
+    pub rotation: usize,
+    pub mirror: bool,
+}
+
+impl PrefabTransform {
+    pub fn random(r: &mut Rand) -> Self {
+        Self {
+            rotation: r.range_n(0, 4) as usize,
+            mirror: r.bool(0.5),
+        }
+    }
+}
+
+impl Prefab {
+    // the terrain of every tile of the prefab, turned and mirrored
+    pub fn grid(&self, transform: PrefabTransform) -> Grid<Option<Terrain>> {
+        legend_grid(&self.legend, &self.rows, transform)
+    }
+
+    pub fn fits(&self, biome: Biome, poi: Option<PoiKind>) -> bool {
+        let fits_biome = self.biomes.is_empty() || self.biomes.contains(&biome);
+        let fits_poi = match poi {
+            Some(kind) => self.pois.contains(&kind),
+            None => self.pois.is_empty(),
+        };
+
+        fits_biome && fits_poi
+    }
+}
+
+// Read rows of characters through a legend, top row first, then mirror and
+// turn the result.
+pub fn legend_grid(
+    legend: &HashMap<char, Terrain>,
+    rows: &[String],
+    transform: PrefabTransform,
+) -> Grid<Option<Terrain>> {
+    let height = rows.len();
+    let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+    let mut grid = Grid::init(width, height, None);
+
+    for (i, row) in rows.iter().enumerate() {
+        for (x, ch) in row.chars().enumerate() {
+            let x = match transform.mirror {
+                true => width - 1 - x,
+                false => x,
+            };
+
+            grid.set(x, height - 1 - i, legend.get(&ch).copied());
+        }
+    }
+
+    for _ in 0..transform.rotation % 4 {
+        let (w, h) = (grid.width(), grid.height());
+        let mut turned = Grid::init(h, w, None);
+
+        for x in 0..w {
+            for y in 0..h {
+                turned.set(y, w - 1 - x, *grid.get(x, y).unwrap());
+            }
+        }
+
+        grid = turned;
+    }
+
+    grid
+}
+
+// Stamp the prefab into the terrain with its south west corner at (x, y).
+pub fn stamp_prefab(terrain: &mut Grid<Terrain>, prefab: &Grid<Option<Terrain>>, x: usize, y: usize) {
+    for px in 0..prefab.width() {
+        for py in 0..prefab.height() {
+            if let Some(t) = prefab.get(px, py).unwrap() {
+                terrain.set(x + px, y + py, *t);
+            }
+        }
+    }
+}
+
+// Find somewhere the prefab can go without landing on water, a road, a
+// railroad or a building, and a tile in from the zone edge. Prefabs go in
+// before the stairs are carved, so the spots in `stairs` are kept out of the
+// prefab and the tile around it, where its walls could shut them in.
+pub fn find_prefab_spot(
+    terrain: &Grid<Terrain>,
+    prefab: &Grid<Option<Terrain>>,
+    stairs: &[(usize, usize)],
+    r: &mut Rand,
+) -> Option<(usize, usize)> {
+    let (w, h) = (prefab.width(), prefab.height());
+
+    if w + 2 >= zone_size().0 || h + 2 >= zone_size().1 {
+        return None;
+    }
+
+    let is_clear = |t: &Terrain| {
+        !t.is_water()
+            && !t.is_building()
+            && !matches!(t, Terrain::Footpath | Terrain::Rail | Terrain::Platform)
+    };
+
+    (0..PREFAB_PLACEMENT_TRIES).find_map(|_| {
+        let x = r.range_n(1, (zone_size().0 - w - 1) as i32) as usize;
+        let y = r.range_n(1, (zone_size().1 - h - 1) as i32) as usize;
+
+        if stairs.iter().any(|(sx, sy)| *sx + 1 >= x && *sx <= x + w && *sy + 1 >= y && *sy <= y + h) {
+            return None;
+        }
+
+        let fits = (0..w).all(|px| {
+            (0..h).all(|py| {
+                prefab.get(px, py).unwrap().is_none() || is_clear(terrain.get(x + px, y + py).unwrap())
+            })
+        });
+
+        fits.then_some((x, y))
+    })
+}
+
+// The loaded prefabs by file name, handed to the zone builders. Rebuilt
+// whenever a prefab file changes, so edits show up in the next zone that is
+// built.
+#[derive(Resource, Clone, Default)]
+pub struct PrefabLibrary {
+    prefabs: Arc<HashMap<String, Prefab>>,
+    is_loaded: bool,
+}
+
+impl PrefabLibrary {
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Prefab)> {
+        self.prefabs.iter()
+    }
+}
+
+// every file under `assets/prefabs/`
+#[derive(Resource)]
+pub struct PrefabFolder(Handle<LoadedFolder>);
+
+#[derive(Default)]
+pub struct PrefabLoader;
+
+impl AssetLoader for PrefabLoader {
+    type Asset = Prefab;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Prefab, Self::Error> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).await?;
+
+        Ok(ron::de::from_bytes::<Prefab>(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["prefab.ron"]
+    }
+}
+
+pub fn load_prefabs(mut cmds: Commands, asset_server: Res<AssetServer>) {
+    cmds.insert_resource(PrefabFolder(asset_server.load_folder("prefabs")));
+}
+
+// zones built before the prefabs are in would come out different for the
+// same seed, so the game waits for them
+pub fn prefabs_loaded(library: Res<PrefabLibrary>) -> bool {
+    library.is_loaded
+}
+
+pub fn on_prefab_changed(
+    mut e_prefabs: EventReader<AssetEvent<Prefab>>,
+    asset_server: Res<AssetServer>,
+    folder: Res<PrefabFolder>,
+    folders: Res<Assets<LoadedFolder>>,
+    assets: Res<Assets<Prefab>>,
+    mut library: ResMut<PrefabLibrary>,
+) {
+    if library.is_loaded && e_prefabs.is_empty() {
+        return;
+    }
+
+    let Some(loaded) = folders
+        .get(&folder.0)
+        .filter(|_| asset_server.is_loaded_with_dependencies(&folder.0))
+    else {
+        return;
+    };
+    e_prefabs.clear();
+
+    let prefabs = loaded
+        .handles
+        .iter()
+        .filter_map(|handle| {
+            let name = asset_name(handle, ".prefab.ron")?;
+            let prefab = assets.get(&handle.clone().try_typed::<Prefab>().ok()?)?;
+
+            Some((name, prefab.clone()))
+        })
+        .collect::<HashMap<_, _>>();
+
+    info!("prefabs loaded {}", prefabs.len());
+
+    library.prefabs = Arc::new(prefabs);
+    library.is_loaded = true;
+}
+
+// the file name of an asset, without its extension
+pub fn asset_name(handle: &UntypedHandle, extension: &str) -> Option<String> {
+    let file_name = handle.path()?.path().file_name()?.to_str()?;
+
+    file_name.strip_suffix(extension).map(|n| n.to_string())
+}
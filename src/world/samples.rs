@@ -0,0 +1,168 @@
+use std::{collections::HashMap, sync::Arc};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, LoadContext, LoadedFolder},
+    prelude::*,
+};
+use serde::Deserialize;
+
+use crate::common::Grid;
+
+use super::{asset_name, legend_grid, Biome, PoiKind, PrefabTransform, Terrain};
+
+pub struct SamplePlugin;
+
+impl Plugin for SamplePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Sample>()
+            .init_asset_loader::<SampleLoader>()
+            .init_resource::<SampleLibrary>()
+            .add_systems(Startup, load_samples)
+            .add_systems(Update, on_sample_changed);
+    }
+}
+
+// A small hand-drawn patch of ground the wave function collapse builder
+// learns which terrain sits next to which from. Written the same way as a
+// prefab, every character has to be in the legend.
+#[derive(Asset, TypePath, Clone, Deserialize)]
+pub struct Sample {
+    pub legend: HashMap<char, Terrain>,
+    // top row first, as it reads in the file
+    pub rows: Vec<String>,
+    // also learn from the sample turned and mirrored, for ground that has
+    // no grain to it
+    #[serde(default)]
+    pub symmetric: bool,
+    // used in these biomes, or any when empty
+    #[serde(default)]
+    pub biomes: Vec<Biome>,
+    // used in zones with one of these points of interest, or any when empty
+    #[serde(default)]
+    pub pois: Vec<PoiKind>,
+}
+
+impl Sample {
+    // the sample as it is drawn, and turned and mirrored when symmetric
+    pub fn grids(&self) -> Vec<Grid<Option<Terrain>>> {
+        let transforms = match self.symmetric {
+            true => (0..8)
+                .map(|i| PrefabTransform {
+                    rotation: i % 4,
+                    mirror: i >= 4,
+                })
+                .collect(),
+            false => vec![PrefabTransform::default()],
+        };
+
+        transforms
+            .into_iter()
+            .map(|t| legend_grid(&self.legend, &self.rows, t))
+            .collect()
+    }
+
+    pub fn fits(&self, biome: Biome, poi: Option<PoiKind>) -> bool {
+        let fits_biome = self.biomes.is_empty() || self.biomes.contains(&biome);
+        let fits_poi = self.pois.is_empty() || poi.is_some_and(|kind| self.pois.contains(&kind));
+
+        fits_biome && fits_poi
+    }
+}
+
+// The loaded samples by name, handed to the zone builders. Rebuilt whenever
+// a sample file changes, so edits show up in the next zone that is built.
+#[derive(Resource, Clone, Default)]
+pub struct SampleLibrary {
+    samples: Arc<HashMap<String, Sample>>,
+    is_loaded: bool,
+}
+
+impl SampleLibrary {
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Sample)> {
+        self.samples.iter()
+    }
+}
+
+// every file under `assets/samples/`
+#[derive(Resource)]
+pub struct SampleFolder(Handle<LoadedFolder>);
+
+#[derive(Default)]
+pub struct SampleLoader;
+
+impl AssetLoader for SampleLoader {
+    type Asset = Sample;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Sample, Self::Error> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).await?;
+
+        let sample = ron::de::from_bytes::<Sample>(&bytes)?;
+
+        // a character missing from the legend would leave a hole the
+        // builder learns nothing from
+        if let Some(ch) = sample.rows.iter().flat_map(|r| r.chars()).find(|ch| !sample.legend.contains_key(ch)) {
+            return Err(format!("'{}' is not in the legend", ch).into());
+        }
+
+        Ok(sample)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sample.ron"]
+    }
+}
+
+pub fn load_samples(mut cmds: Commands, asset_server: Res<AssetServer>) {
+    cmds.insert_resource(SampleFolder(asset_server.load_folder("samples")));
+}
+
+// the wave function collapse builder learns from every sample, so the game
+// waits for them the same as for prefabs
+pub fn samples_loaded(library: Res<SampleLibrary>) -> bool {
+    library.is_loaded
+}
+
+pub fn on_sample_changed(
+    mut e_samples: EventReader<AssetEvent<Sample>>,
+    asset_server: Res<AssetServer>,
+    folder: Res<SampleFolder>,
+    folders: Res<Assets<LoadedFolder>>,
+    assets: Res<Assets<Sample>>,
+    mut library: ResMut<SampleLibrary>,
+) {
+    if library.is_loaded && e_samples.is_empty() {
+        return;
+    }
+
+    let Some(loaded) = folders
+        .get(&folder.0)
+        .filter(|_| asset_server.is_loaded_with_dependencies(&folder.0))
+    else {
+        return;
+    };
+    e_samples.clear();
+
+    let samples = loaded
+        .handles
+        .iter()
+        .filter_map(|handle| {
+            let name = asset_name(handle, ".sample.ron")?;
+            let sample = assets.get(&handle.clone().try_typed::<Sample>().ok()?)?;
+
+            Some((name, sample.clone()))
+        })
+        .collect::<HashMap<_, _>>();
+
+    info!("samples loaded {}", samples.len());
+
+    library.samples = Arc::new(samples);
+    library.is_loaded = true;
+}
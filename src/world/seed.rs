@@ -0,0 +1,45 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::common::Rand;
+
+// The seed every world rng and noise source is derived from. Chosen once
+// on new-game (or overridden from the cli/env) and stored with the save.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WorldSeed(pub u64);
+
+impl WorldSeed {
+    pub fn random() -> Self {
+        Self(Rand::new().next_u64())
+    }
+
+    // `--seed <n>` on the command line, or the `SEED` env var
+    pub fn from_override() -> Option<Self> {
+        let arg = std::env::args().skip_while(|a| a != "--seed").nth(1);
+
+        let value = arg.or_else(|| std::env::var("SEED").ok())?;
+
+        match value.trim().parse::<u64>() {
+            Ok(v) => Some(Self(v)),
+            Err(_) => {
+                warn!("invalid world seed override '{}'", value);
+                None
+            }
+        }
+    }
+
+    // mix a value into the world seed (splitmix64), so that nearby
+    // values still produce unrelated seeds.
+    pub fn mix(&self, v: u64) -> u64 {
+        let mut z = self.0 ^ v.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // seed for everything generated inside a single zone
+    #[inline]
+    pub fn zone(&self, zone_idx: usize) -> u64 {
+        self.mix(zone_idx as u64)
+    }
+}
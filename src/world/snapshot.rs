@@ -2,7 +2,7 @@ use bevy::{math::vec3, prelude::*};
 
 use crate::{
     camera::{CursorPosition, Layer, MainCamera}, common::Grid, projection::{
-        world_to_zone_local, zone_local_to_world, zone_transform_center, ZONE_SIZE, Z_LAYER_SNAPSHOT
+        world_to_zone_local, zone_local_to_world, zone_size, zone_transform_center, Z_LAYER_SNAPSHOT
     }, rendering::{Glyph, Palette, Position, Tile}, GameState
 };
 
@@ -93,8 +93,8 @@ pub fn enter_snapshot_mode(
 
     let mut tiles = vec![];
 
-    for x in 0..ZONE_SIZE.0 {
-        for y in 0..ZONE_SIZE.1 {
+    for x in 0..zone_size().0 {
+        for y in 0..zone_size().1 {
             let wpos = zone_local_to_world(mode.idx, x, y);
 
             let mut e = cmds.spawn((
@@ -117,7 +117,7 @@ pub fn enter_snapshot_mode(
 
     cmds.insert_resource(SnapshotTiles {
         container,
-        tiles: Grid::init_from_vec(ZONE_SIZE.0, ZONE_SIZE.1, tiles),
+        tiles: Grid::init_from_vec(zone_size().0, zone_size().1, tiles),
     });
 
     e_change_snapshot.send(UpdateSnapshotTilesEvent { snap_idx: 0 });
@@ -172,14 +172,14 @@ pub fn on_update_snapshot_tiles(
     mut q_glyphs: Query<&mut Glyph, With<SnapshotTile>>,
 ) {
     for e in e_change_snapshot.read() {
-        info!("redraw tiles! {}", e.snap_idx);
-
         let Some(snapshot) = mode.snapshots.get(e.snap_idx) else {
             continue;
         };
 
-        for x in 0..ZONE_SIZE.0 {
-            for y in 0..ZONE_SIZE.1 {
+        info!("redraw tiles! {} {}", e.snap_idx, snapshot.name);
+
+        for x in 0..zone_size().0 {
+            for y in 0..zone_size().1 {
                 let Some(snap_color) = snapshot.data.get(x, y) else {
                     continue;
                 };
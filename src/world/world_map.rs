@@ -0,0 +1,234 @@
+use bevy::prelude::*;
+
+use crate::{
+    camera::Layer,
+    projection::{map_size, zone_idx, zone_xyz},
+    rendering::{Glyph, Palette, Position, Text, Tile},
+    ui::UiBox,
+    GameState,
+};
+
+use super::{Discoveries, EdgeCrossing, Map, PoiKind, Terrain, Zones};
+
+// where the world map box sits on screen, and how far in from its
+// corner the map itself starts
+const WORLD_MAP_ORIGIN: (usize, usize) = (2, 2);
+const WORLD_MAP_PADDING: usize = 2;
+
+pub struct WorldMapPlugin;
+
+impl Plugin for WorldMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WorldMapView>()
+            .add_systems(Update, open_world_map.run_if(in_state(GameState::Playing)))
+            .add_systems(OnEnter(GameState::WorldMap), enter_world_map)
+            .add_systems(OnExit(GameState::WorldMap), exit_world_map)
+            .add_systems(
+                Update,
+                (world_map_controls, draw_world_map)
+                    .chain()
+                    .run_if(in_state(GameState::WorldMap)),
+            );
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct WorldMapView {
+    // the z-level being looked at
+    pub z: usize,
+    container: Option<Entity>,
+}
+
+pub fn open_world_map(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    if keys.just_pressed(KeyCode::KeyM) {
+        next_game_state.set(GameState::WorldMap);
+    }
+}
+
+// start out looking at the level the player is on
+pub fn enter_world_map(mut view: ResMut<WorldMapView>, zones: Res<Zones>) {
+    view.z = zone_xyz(zones.player).2;
+}
+
+pub fn exit_world_map(mut cmds: Commands, mut view: ResMut<WorldMapView>) {
+    if let Some(container) = view.container.take() {
+        cmds.entity(container).despawn_recursive();
+    }
+}
+
+pub fn world_map_controls(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut view: ResMut<WorldMapView>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    if keys.just_pressed(KeyCode::KeyM) {
+        next_game_state.set(GameState::Playing);
+    }
+
+    if keys.just_pressed(KeyCode::KeyE) && view.z > 0 {
+        view.z -= 1;
+    }
+
+    if keys.just_pressed(KeyCode::KeyQ) && view.z < map_size().2 - 1 {
+        view.z += 1;
+    }
+
+    keys.reset_all();
+}
+
+// Draw the level being looked at. Every zone is one glyph, with the edges
+// between zones in the gaps around it, so rivers, roads and railroads can be
+// followed across the map. Only what the player has seen is shown.
+pub fn draw_world_map(
+    mut cmds: Commands,
+    mut view: ResMut<WorldMapView>,
+    map: Res<Map>,
+    zones: Res<Zones>,
+    discoveries: Res<Discoveries>,
+) {
+    if !view.is_changed() {
+        return;
+    }
+
+    if let Some(container) = view.container.take() {
+        cmds.entity(container).despawn_recursive();
+    }
+
+    let (width, height, _) = map_size();
+    let z = view.z;
+    let pad = WORLD_MAP_PADDING;
+    let is_known = |x: usize, y: usize| discoveries.zones.contains(&zone_idx(x, y, z));
+
+    let title = match z {
+        0 => "World Map".to_string(),
+        _ => format!("World Map - Depth {}", z),
+    };
+
+    let container = cmds
+        .spawn((
+            UiBox::new(width * 2 + 1 + pad * 2, height * 2 + 1 + pad * 2).title(title),
+            Position::new(WORLD_MAP_ORIGIN.0, WORLD_MAP_ORIGIN.1, 0, Layer::Ui),
+        ))
+        .id();
+
+    let mut glyphs = vec![];
+
+    for x in 0..width {
+        for y in 0..height {
+            let glyph = match zone_idx(x, y, z) == zones.player {
+                true => Glyph::new(Tile::Cowboy, Palette::White, Palette::Yellow).bg(Palette::Black),
+                false => zone_glyph(&map, x, y, z, is_known(x, y)),
+            };
+
+            glyphs.push((x * 2 + 1, y * 2 + 1, glyph));
+        }
+    }
+
+    // edges sit between the two zones that share them, and are known once
+    // either of those zones is
+    for x in 0..=width {
+        for y in 0..=height {
+            let Some(edges) = map.get_edges(x, y, z) else {
+                continue;
+            };
+
+            let crossings = [edges.river, edges.footpath, edges.railroad];
+            let south_known = x < width && (is_known(x, y.min(height - 1)) || (y > 0 && is_known(x, y - 1)));
+            let west_known = y < height && (is_known(x.min(width - 1), y) || (x > 0 && is_known(x - 1, y)));
+
+            if let Some(glyph) = edge_glyph(&crossings, true).filter(|_| south_known) {
+                glyphs.push((x * 2 + 1, y * 2, glyph));
+            }
+
+            if let Some(glyph) = edge_glyph(&crossings, false).filter(|_| west_known) {
+                glyphs.push((x * 2, y * 2 + 1, glyph));
+            }
+        }
+    }
+
+    for (x, y, glyph) in glyphs {
+        cmds.spawn((glyph, Position::new(x + pad, y + pad, 0, Layer::Ui)))
+            .set_parent(container);
+    }
+
+    cmds.spawn((
+        Text::new("[M] close  [E] up  [Q] down").bg(Palette::Black).fg1(Palette::Gray),
+        Position::new(pad, 1, 0, Layer::Ui),
+    ))
+    .set_parent(container);
+
+    view.container = Some(container);
+}
+
+// a discovered zone shows its point of interest, or failing that what the
+// land is like. Undiscovered ones are shrouded.
+fn zone_glyph(map: &Map, x: usize, y: usize, z: usize, is_known: bool) -> Glyph {
+    let zone = map.get_zone(x, y, z);
+    let poi = zone.and_then(|o| o.poi.as_ref()).filter(|_| is_known);
+
+    let terrain = match (poi, z) {
+        (Some(poi), _) => return poi_glyph(poi.kind),
+        (None, 0) => map.get_biome(x, y).features()[0].0,
+        (None, _) if zone.is_some_and(|o| o.structure.is_some()) && is_known => Terrain::Wall,
+        (None, _) => Terrain::CaveFloor,
+    };
+
+    let (bg, fg) = terrain.colors();
+
+    Glyph {
+        tile: Some(terrain.tile()),
+        fg1: fg,
+        fg2: None,
+        bg,
+        outline: None,
+        is_shrouded: !is_known,
+    }
+}
+
+fn poi_glyph(kind: PoiKind) -> Glyph {
+    let (tile, color) = match kind {
+        PoiKind::Capital => (Tile::Sign, Palette::Yellow),
+        PoiKind::Town => (Tile::Sign, Palette::White),
+        PoiKind::GoldMine => (Tile::LadderHole, Palette::Yellow),
+        PoiKind::Mine => (Tile::LadderHole, Palette::Gray),
+        PoiKind::Ranch => (Tile::Fence, Palette::Brown),
+        PoiKind::Fort => (Tile::Fence, Palette::Red),
+        PoiKind::Camp => (Tile::Campfire, Palette::Orange),
+        PoiKind::Ruin => (Tile::Boulder, Palette::Gray),
+        PoiKind::Cemetery => (Tile::Sign, Palette::Gray),
+    };
+
+    Glyph::new(tile, color, color).bg(Palette::Black)
+}
+
+// railroads are drawn over roads, and roads over rivers
+fn edge_glyph(crossings: &[EdgeCrossing; 3], is_south: bool) -> Option<Glyph> {
+    let [river, footpath, railroad] = crossings;
+
+    let terrain = if railroad.get(is_south).is_some() {
+        Terrain::Rail
+    } else if footpath.get(is_south).is_some() {
+        Terrain::Footpath
+    } else if river.get(is_south).is_some() {
+        match river.width(is_south) {
+            1 => Terrain::Creek,
+            _ => Terrain::River,
+        }
+    } else {
+        return None;
+    };
+
+    let (bg, fg) = terrain.colors();
+
+    Some(Glyph {
+        tile: Some(terrain.tile()),
+        fg1: fg,
+        fg2: None,
+        bg,
+        outline: None,
+        is_shrouded: false,
+    })
+}
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 
 use crate::{
-    camera::Layer, common::Grid, player::PlayerMovedEvent, projection::{world_to_zone_idx, zone_local_to_world, ZONE_SIZE, Z_LAYER_GROUND}, rendering::{Glyph, Position}, save::{save_zone, try_load_zone}, world::{SimpleZoneBuilder, ZoneBuilder, ENABLE_ZONE_SNAPSHOTS}
+    camera::Layer, common::Grid, player::PlayerMovedEvent, projection::{world_to_zone_idx, zone_local_to_world, zone_size, Z_LAYER_GROUND}, rendering::{Glyph, Position}, save::{save_zone, try_load_zone}, world::{PrefabLibrary, SampleLibrary, WorldConfig, WorldSeed, ZoneBuilders, ENABLE_ZONE_SNAPSHOTS}
 };
 
 use super::{Map, Zone, ZoneData, ZoneSnapshotsEvent, ZoneStatus, Zones};
@@ -28,17 +28,27 @@ pub fn on_load_zone(
     mut e_spawn_zone: EventWriter<SpawnZoneEvent>,
     mut e_zone_snapshots: EventWriter<ZoneSnapshotsEvent>,
     map: Res<Map>,
+    seed: Res<WorldSeed>,
+    config: Res<WorldConfig>,
+    builders: Res<ZoneBuilders>,
+    prefabs: Res<PrefabLibrary>,
+    samples: Res<SampleLibrary>,
 ) {
     for LoadZoneEvent(zone_idx) in e_load_zone.read() {
         info!("load zone! {}", zone_idx);
 
-        if let Some(save_data) = try_load_zone(*zone_idx) {
+        let saved = match builders.is_overridden(*zone_idx) {
+            true => None,
+            false => try_load_zone(*zone_idx, *seed, *config),
+        };
+
+        if let Some(save_data) = saved {
             e_spawn_zone.send(SpawnZoneEvent { data: save_data });
             continue;
         };
 
-        let mut builder = SimpleZoneBuilder::default();
-        let constraints = map.get_zone_constraints(*zone_idx);
+        let mut builder = builders.get(&map, *zone_idx);
+        let constraints = map.get_zone_constraints(*zone_idx, &prefabs, &samples);
 
         let data = builder.build(constraints);
 
@@ -56,6 +66,8 @@ pub fn on_load_zone(
 pub fn on_unload_zone(
     mut e_unload_zone: EventReader<UnloadZoneEvent>,
     mut cmds: Commands,
+    seed: Res<WorldSeed>,
+    config: Res<WorldConfig>,
     q_zones: Query<(Entity, &Zone)>,
 ) {
     for UnloadZoneEvent(zone_idx) in e_unload_zone.read() {
@@ -65,7 +77,7 @@ pub fn on_unload_zone(
             continue;
         };
 
-        save_zone(&zone.to_save());
+        save_zone(*seed, *config, zone.to_save());
 
         cmds.entity(zone_e).despawn_recursive();
     }
@@ -85,8 +97,8 @@ pub fn on_spawn_zone(mut e_spawn_zone: EventReader<SpawnZoneEvent>, mut cmds: Co
 
         let mut tiles = vec![];
 
-        for x in 0..ZONE_SIZE.0 {
-            for y in 0..ZONE_SIZE.1 {
+        for x in 0..zone_size().0 {
+            for y in 0..zone_size().1 {
                 let terrain = e.data.terrain.get(x, y).unwrap();
                 let wpos = zone_local_to_world(e.data.idx, x, y);
                 let (bg, fg) = terrain.colors();
@@ -111,7 +123,7 @@ pub fn on_spawn_zone(mut e_spawn_zone: EventReader<SpawnZoneEvent>, mut cmds: Co
             }
         }
 
-        let tile_grid = Grid::init_from_vec(ZONE_SIZE.0, ZONE_SIZE.1, tiles);
+        let tile_grid = Grid::init_from_vec(zone_size().0, zone_size().1, tiles);
         let zone = Zone::new(e.data.idx, e.data.terrain.clone(), tile_grid);
 
         cmds.entity(zone_e).insert(zone);
@@ -136,6 +148,30 @@ pub fn on_set_zone_status(
     }
 }
 
+// Debug key, build the zone the player is in again with the next builder.
+// The zone is unloaded, and loaded back in with the new builder.
+pub fn cycle_zone_builder(
+    keys: Res<ButtonInput<KeyCode>>,
+    zones: Res<Zones>,
+    map: Res<Map>,
+    mut builders: ResMut<ZoneBuilders>,
+    mut e_unload_zone: EventWriter<UnloadZoneEvent>,
+) {
+    if !keys.just_pressed(KeyCode::KeyB) {
+        return;
+    }
+
+    let idx = zones.player;
+    let names = builders.names();
+    let current = names.iter().position(|n| *n == builders.pick(&map, idx)).unwrap_or(0);
+    let next = names[(current + 1) % names.len()];
+
+    info!("rebuilding zone {} with {}", idx, next);
+
+    builders.set_override(idx, next);
+    e_unload_zone.send(UnloadZoneEvent(idx));
+}
+
 // check when player moves to a different zone and set it as active
 pub fn on_player_move(
     mut e_player_moved: EventReader<PlayerMovedEvent>,